@@ -1,13 +1,83 @@
+// Grimoire keeps one native messaging port open. Requests carry an id so
+// several can be in flight at once, and the port also delivers vault events.
+let port = null;
+let nextRequestId = 1;
+const inFlight = new Map();
+let vaultState = "disconnected";
+
+function getPort() {
+    if (port) {
+        return port;
+    }
+    
+    port = browser.runtime.connectNative("com.grimoire.native");
+    port.onMessage.addListener(handleNativeMessage);
+    port.onDisconnect.addListener((disconnected) => {
+        const reason = disconnected.error ? disconnected.error.message : "Disconnected";
+        port = null;
+        for (const request of inFlight.values()) {
+            request.reject(new Error(reason));
+        }
+        inFlight.clear();
+        setVaultState("disconnected");
+    });
+    port.postMessage({ action: "subscribe" });
+    return port;
+}
+
+function handleNativeMessage(message) {
+    if (message.event) {
+        handleVaultEvent(message.event);
+        return;
+    }
+    
+    const request = inFlight.get(message.id);
+    if (request) {
+        inFlight.delete(message.id);
+        request.resolve(message);
+    }
+}
+
+function handleVaultEvent(event) {
+    if (event === "locked") {
+        setVaultState("locked");
+    } else if (event === "unlocked") {
+        setVaultState("unlocked");
+    }
+    
+    // The popup may not be open, that's ok
+    browser.runtime.sendMessage({
+        action: "vault_event",
+        event: event,
+        state: vaultState
+    }).catch(() => {});
+}
+
+function setVaultState(state) {
+    vaultState = state;
+    browser.browserAction.setBadgeText({ text: state === "locked" ? "LOCK" : "" });
+}
+
+function sendToGrimoire(message) {
+    return new Promise((resolve, reject) => {
+        const id = nextRequestId++;
+        inFlight.set(id, { resolve, reject });
+        try {
+            getPort().postMessage({ ...message, id: id });
+        } catch (error) {
+            inFlight.delete(id);
+            reject(error);
+        }
+    });
+}
+
 // Request credentials for a domain
 async function getCredentials(domain) {
     try {
-        const response = await browser.runtime.sendNativeMessage(
-            "com.grimoire.native",
-            {
-                action: "get_credentials",
-                domain: domain
-            }
-        );
+        const response = await sendToGrimoire({
+            action: "get_credentials",
+            domain: domain
+        });
         
         if (response.ok) {
             return {
@@ -25,15 +95,12 @@ async function getCredentials(domain) {
 // Send new credentials to Grimoire for a domain
 async function sendCredentials(domain, username, password) {
     try {
-        const response = await browser.runtime.sendNativeMessage(
-            "com.grimoire.native",
-            {
-                action: "set_credentials",
-                domain: domain,
-                username: username,
-                password: password
-            }
-        );
+        const response = await sendToGrimoire({
+            action: "set_credentials",
+            domain: domain,
+            username: username,
+            password: password
+        });
         
         if (response.ok) {
             return {
@@ -48,15 +115,37 @@ async function sendCredentials(domain, username, password) {
     }
 }
 
+// Ask Grimoire for a fresh password, optionally queueing it for the domain
+async function generatePassword(domain, options = {}) {
+    try {
+        const response = await sendToGrimoire({
+            action: "generate_password",
+            domain: domain,
+            username: options.username,
+            length: options.length,
+            symbols: options.symbols,
+            save: options.save
+        });
+        
+        if (response.ok) {
+            return {
+                password: response.password,
+                message: response.message
+            };
+        } else {
+            throw new Error(response.error || "Unknown error");
+        }
+    } catch (error) {
+        throw new Error(`Could not connect to Grimoire: ${error.message}`);
+    }
+}
+
 // Ping to test connection
 async function ping() {
     try {
-        const response = await browser.runtime.sendNativeMessage(
-            "com.grimoire.native",
-            {
-                action: "ping"
-            }
-        );
+        const response = await sendToGrimoire({
+            action: "ping"
+        });
         return response.ok;
     } catch (error) {
         return false;
@@ -101,6 +190,30 @@ browser.runtime.onMessage.addListener((message, sender, sendResponse) => {
         return true;
     }
     
+    if (message.action === "generate_password") {
+        generatePassword(message.domain, message.options)
+            .then(result => {
+                sendResponse({
+                    success: true,
+                    password: result.password,
+                    message: result.message
+                });
+            })
+            .catch(error => {
+                sendResponse({
+                    success: false,
+                    error: error.message
+                });
+            });
+        
+        return true;
+    }
+    
+    if (message.action === "get_status") {
+        sendResponse({ state: vaultState });
+        return false;
+    }
+    
     if (message.action === "ping") {
         ping()
             .then(success => {
@@ -127,6 +240,13 @@ browser.runtime.onMessage.addListener((message, sender, sendResponse) => {
     }
 });
 
+// Connect straight away so lock state is known before the first request
+try {
+    getPort();
+} catch (e) {
+    // Native host not installed, requests will report it
+}
+
 // Optional: Auto-fill on page load
 browser.tabs.onUpdated.addListener((tabId, changeInfo, tab) => {
     if (changeInfo.status === 'complete' && tab.url) {
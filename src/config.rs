@@ -0,0 +1,322 @@
+use crate::generator::GeneratorProfile;
+use crate::keymap::KeymapConfig;
+use crate::theme::ThemeConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub master_password_file: PathBuf,
+    pub password_store: PathBuf,
+    /// Cards per grid row, or as many as fit the terminal when unset
+    pub secrets_per_row: Option<usize>,
+    pub sort_mode: SortMode,
+    pub view: ViewMode,
+    /// Master passwords estimated below this many bits are refused
+    pub min_master_password_entropy: f64,
+    /// Days deleted secrets stay in the trash, or forever when 0
+    pub trash_retention_days: u32,
+    /// Capture the mouse for clicking and scrolling, instead of leaving it
+    /// to the terminal for selecting text
+    pub mouse: bool,
+    pub password_generator_length: u8,
+    pub password_generator_symbols: bool,
+    /// Generator rules to choose from. A "default" profile is built from
+    /// the two settings above unless one is given here
+    #[serde(default = "GeneratorProfile::presets")]
+    pub generator_profiles: Vec<GeneratorProfile>,
+    pub health: HealthConfig,
+    pub login: LoginConfig,
+    pub keymap: KeymapConfig,
+    pub theme: ThemeConfig,
+    pub ipc: IpcConfig,
+    pub native_messaging: NativeMessagingConfig,
+}
+
+/// Orders the main grid can be shown in
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    Name,
+    LastModified,
+    Created,
+    RecentlyUsed,
+    Tag,
+}
+
+impl SortMode {
+    pub const ALL: [SortMode; 5] = [
+        SortMode::Name,
+        SortMode::LastModified,
+        SortMode::Created,
+        SortMode::RecentlyUsed,
+        SortMode::Tag,
+    ];
+
+    pub fn next(&self) -> SortMode {
+        let idx = SortMode::ALL
+            .iter()
+            .position(|mode| mode == self)
+            .unwrap_or(0);
+        SortMode::ALL[(idx + 1) % SortMode::ALL.len()]
+    }
+
+    /// The name used in the config file
+    pub fn key(&self) -> &'static str {
+        match self {
+            SortMode::Name => "name",
+            SortMode::LastModified => "last_modified",
+            SortMode::Created => "created",
+            SortMode::RecentlyUsed => "recently_used",
+            SortMode::Tag => "tag",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortMode::Name => "name",
+            SortMode::LastModified => "last modified",
+            SortMode::Created => "created",
+            SortMode::RecentlyUsed => "recently used",
+            SortMode::Tag => "tag",
+        }
+    }
+}
+
+/// Layouts of the main screen
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewMode {
+    /// Cards showing every field
+    Grid,
+    /// Names in a list, with the selected secret beside them
+    List,
+}
+
+impl ViewMode {
+    /// The name used in the config file
+    pub fn key(&self) -> &'static str {
+        match self {
+            ViewMode::Grid => "grid",
+            ViewMode::List => "list",
+        }
+    }
+}
+
+/// Thresholds for the vault health report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthConfig {
+    /// Entries unchanged for longer than this are reported as old
+    pub max_password_age_days: u32,
+    /// Passwords estimated below this many bits are reported as weak
+    pub weak_password_entropy: f64,
+    /// A local Have I Been Pwned SHA-1 dump, ordered by hash
+    pub breach_file: Option<PathBuf>,
+}
+
+impl Default for HealthConfig {
+    fn default() -> HealthConfig {
+        HealthConfig {
+            max_password_age_days: 365,
+            weak_password_entropy: 40.0,
+            breach_file: None,
+        }
+    }
+}
+
+/// Limits on guessing the master password
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoginConfig {
+    /// Wrong passwords in a row before `on_max_attempts`, or no limit when 0
+    pub max_attempts: u32,
+    pub on_max_attempts: MaxAttemptsAction,
+    /// How long a lockout refuses every password
+    pub lockout_minutes: u32,
+    /// Wrong passwords, lockouts and self-destructs are written here
+    pub log_file: PathBuf,
+}
+
+impl Default for LoginConfig {
+    fn default() -> LoginConfig {
+        LoginConfig {
+            max_attempts: 0,
+            on_max_attempts: MaxAttemptsAction::Lockout,
+            lockout_minutes: 60,
+            log_file: Config::data_dir().join("login.log"),
+        }
+    }
+}
+
+/// What happens once `max_attempts` wrong passwords have been tried
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaxAttemptsAction {
+    /// Refuse every password for `lockout_minutes`, again after each miss
+    Lockout,
+    /// Delete the vault, its hash and hint
+    SelfDestruct,
+}
+
+/// Which IPC actions launchers and editor plugins may use
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IpcConfig {
+    /// `search`: entry IDs, names and non-sensitive fields
+    pub allow_search: bool,
+    /// `get_entry`: every field of an entry, passwords included
+    pub allow_get_entry: bool,
+    /// `get_field`: a single field of an entry
+    pub allow_get_field: bool,
+}
+
+impl Default for IpcConfig {
+    fn default() -> IpcConfig {
+        IpcConfig {
+            allow_search: true,
+            allow_get_entry: false,
+            allow_get_field: false,
+        }
+    }
+}
+
+/// Settings for the grimoire-forwarder browser bridge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NativeMessagingConfig {
+    /// Extension IDs allowed to talk to the forwarder
+    pub allowed_extensions: Vec<String>,
+    pub log_file: PathBuf,
+}
+
+impl Default for NativeMessagingConfig {
+    fn default() -> NativeMessagingConfig {
+        NativeMessagingConfig {
+            allowed_extensions: vec![String::from("grimoire@yourdomain.com")],
+            log_file: Config::data_dir().join("forwarder.log"),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        let data_dir = Config::data_dir();
+        let mut config = Config {
+            master_password_file: data_dir.join("master_password"),
+            password_store: data_dir.join("secrets.json"),
+            secrets_per_row: None,
+            sort_mode: SortMode::Name,
+            view: ViewMode::Grid,
+            min_master_password_entropy: 50.0,
+            trash_retention_days: 30,
+            mouse: true,
+            password_generator_length: 24,
+            password_generator_symbols: true,
+            generator_profiles: GeneratorProfile::presets(),
+            health: HealthConfig::default(),
+            login: LoginConfig::default(),
+            keymap: KeymapConfig::default(),
+            theme: ThemeConfig::default(),
+            ipc: IpcConfig::default(),
+            native_messaging: NativeMessagingConfig::default(),
+        };
+        config.add_default_profile();
+        config
+    }
+}
+
+impl Config {
+    /// Load the config file, falling back to defaults for anything missing.
+    /// A file that doesn't parse is an error rather than ignored, since it
+    /// may hold settings like `[login]` that protect the vault
+    pub fn load() -> Result<Config, String> {
+        let path = Config::config_file();
+        let mut config: Config = match fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Config::default(),
+            Err(e) => return Err(format!("Couldn't read {}: {}", path.display(), e)),
+        };
+        config.add_default_profile();
+        Ok(config)
+    }
+
+    /// Put a "default" generator profile first, following the two legacy
+    /// settings, unless there is one already
+    fn add_default_profile(&mut self) {
+        if !self
+            .generator_profiles
+            .iter()
+            .any(|profile| profile.name == "default")
+        {
+            let default = GeneratorProfile::from_legacy(
+                self.password_generator_length,
+                self.password_generator_symbols,
+            );
+            self.generator_profiles.insert(0, default);
+        }
+    }
+
+    /// The generator profile called `name`, or the first one
+    pub fn generator_profile(&self, name: Option<&str>) -> Option<&GeneratorProfile> {
+        match name {
+            Some(name) => self
+                .generator_profiles
+                .iter()
+                .find(|profile| profile.name == name),
+            None => self.generator_profiles.first(),
+        }
+    }
+
+    /// Change one top-level setting in the config file, leaving everything
+    /// else in it, comments included, as it was
+    pub fn save_setting(key: &str, value: impl Into<toml_edit::Value>) -> io::Result<()> {
+        let path = Config::config_file();
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        let mut document: toml_edit::DocumentMut = text
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        document[key] = toml_edit::value(value);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, document.to_string())
+    }
+
+    /// Wrong password count, kept beside the hash
+    pub fn attempts_file(&self) -> PathBuf {
+        self.master_password_file.with_file_name("login_attempts")
+    }
+
+    /// The master password hint, kept beside the hash
+    pub fn hint_file(&self) -> PathBuf {
+        self.master_password_file
+            .with_file_name("master_password_hint")
+    }
+
+    /// The vault key sealed with the recovery kit's code, kept beside the hash
+    pub fn recovery_file(&self) -> PathBuf {
+        self.master_password_file.with_file_name("recovery_key")
+    }
+
+    pub fn config_file() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("grimoire")
+            .join("config.toml")
+    }
+
+    fn data_dir() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("grimoire")
+    }
+}
@@ -0,0 +1,458 @@
+use crate::wordlist;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_SYMBOLS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+/// Characters that are easy to mistake for one another
+const AMBIGUOUS: &str = "0O1lI|";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GeneratorMode {
+    /// Random characters
+    Password,
+    /// Random words from a diceware list
+    Passphrase,
+}
+
+/// Rules for building a password
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeneratorProfile {
+    pub name: String,
+    pub mode: GeneratorMode,
+    pub min_length: u8,
+    pub max_length: u8,
+    pub upper: bool,
+    pub lower: bool,
+    pub digits: bool,
+    /// Symbols to draw from, none if empty
+    pub symbols: String,
+    pub min_upper: u8,
+    pub min_lower: u8,
+    pub min_digits: u8,
+    pub min_symbols: u8,
+    pub exclude_ambiguous: bool,
+    pub no_repeats: bool,
+    /// "eff_large", "eff_short" or the path of a wordlist file
+    pub wordlist: String,
+    pub words: u8,
+    pub separator: String,
+    pub capitalize: bool,
+    /// Put a random digit at the end of one of the words
+    pub digit: bool,
+}
+
+/// Per-request changes to a profile, from IPC or the command line
+#[derive(Debug, Default)]
+pub struct GeneratorOverrides {
+    pub length: Option<u8>,
+    /// Turn the profile's symbols off, or on with the default set
+    pub symbols: Option<bool>,
+    /// Switch between random characters and words
+    pub passphrase: Option<bool>,
+    pub words: Option<u8>,
+    pub separator: Option<String>,
+}
+
+pub struct GeneratedPassword {
+    pub password: String,
+    /// Bits of entropy for the password's length
+    pub entropy: f64,
+}
+
+impl Default for GeneratorProfile {
+    fn default() -> GeneratorProfile {
+        GeneratorProfile {
+            name: String::from("default"),
+            mode: GeneratorMode::Password,
+            min_length: 24,
+            max_length: 24,
+            upper: true,
+            lower: true,
+            digits: true,
+            symbols: String::from(DEFAULT_SYMBOLS),
+            min_upper: 0,
+            min_lower: 0,
+            min_digits: 0,
+            min_symbols: 0,
+            exclude_ambiguous: false,
+            no_repeats: false,
+            wordlist: String::from("eff_large"),
+            words: 6,
+            separator: String::from("-"),
+            capitalize: false,
+            digit: false,
+        }
+    }
+}
+
+impl GeneratorProfile {
+    /// The profile described by `password_generator_length` and `password_generator_symbols`
+    pub fn from_legacy(length: u8, symbols: bool) -> GeneratorProfile {
+        GeneratorProfile {
+            min_length: length,
+            max_length: length,
+            symbols: if symbols {
+                String::from(DEFAULT_SYMBOLS)
+            } else {
+                String::new()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Profiles available out of the box next to "default"
+    pub fn presets() -> Vec<GeneratorProfile> {
+        vec![
+            GeneratorProfile {
+                name: String::from("strong"),
+                min_length: 28,
+                max_length: 32,
+                min_upper: 2,
+                min_lower: 2,
+                min_digits: 2,
+                min_symbols: 2,
+                ..Default::default()
+            },
+            GeneratorProfile {
+                name: String::from("readable"),
+                min_length: 20,
+                max_length: 20,
+                symbols: String::from("-_.!?"),
+                min_upper: 1,
+                min_lower: 1,
+                min_digits: 1,
+                exclude_ambiguous: true,
+                ..Default::default()
+            },
+            GeneratorProfile {
+                name: String::from("passphrase"),
+                mode: GeneratorMode::Passphrase,
+                ..Default::default()
+            },
+            GeneratorProfile {
+                name: String::from("short-passphrase"),
+                mode: GeneratorMode::Passphrase,
+                wordlist: String::from("eff_short"),
+                words: 5,
+                separator: String::from(" "),
+                capitalize: true,
+                digit: true,
+                ..Default::default()
+            },
+            GeneratorProfile {
+                name: String::from("pin"),
+                min_length: 6,
+                max_length: 6,
+                upper: false,
+                lower: false,
+                symbols: String::new(),
+                ..Default::default()
+            },
+        ]
+    }
+
+    /// The character classes in use, each paired with its minimum count
+    fn classes(&self) -> Vec<(Vec<char>, u8)> {
+        let mut classes = Vec::new();
+        if self.upper {
+            classes.push((('A'..='Z').collect(), self.min_upper));
+        }
+        if self.lower {
+            classes.push((('a'..='z').collect(), self.min_lower));
+        }
+        if self.digits {
+            classes.push((('0'..='9').collect(), self.min_digits));
+        }
+        if !self.symbols.is_empty() {
+            let mut symbols: Vec<char> = self.symbols.chars().collect();
+            symbols.sort();
+            symbols.dedup();
+            classes.push((symbols, self.min_symbols));
+        }
+        if self.exclude_ambiguous {
+            for (pool, _) in &mut classes {
+                pool.retain(|c| !AMBIGUOUS.contains(*c));
+            }
+        }
+        classes
+    }
+
+    /// Check the rules can be satisfied
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mode == GeneratorMode::Passphrase {
+            if self.words == 0 {
+                return Err(format!("Profile {} has no words", self.name));
+            }
+            return wordlist::load(&self.wordlist).map(|_| ());
+        }
+
+        let classes = self.classes();
+        let pool_size: usize = classes.iter().map(|(pool, _)| pool.len()).sum();
+        let required: usize = classes.iter().map(|(_, min)| *min as usize).sum();
+
+        if self.min_length == 0 || self.min_length > self.max_length {
+            return Err(format!("Profile {} has an empty length range", self.name));
+        }
+        if pool_size == 0 {
+            return Err(format!("Profile {} has no characters to use", self.name));
+        }
+        if required > self.min_length as usize {
+            return Err(format!(
+                "Profile {} needs {} characters but may be {} long",
+                self.name, required, self.min_length
+            ));
+        }
+        let short_class = classes.iter().any(|(pool, min)| {
+            (*min > 0 && pool.is_empty()) || (self.no_repeats && *min as usize > pool.len())
+        });
+        if short_class {
+            return Err(format!(
+                "Profile {} asks for more characters of a class than it has",
+                self.name
+            ));
+        }
+        if self.no_repeats && pool_size < self.max_length as usize {
+            return Err(format!(
+                "Profile {} can't fill {} characters without repeats",
+                self.name, self.max_length
+            ));
+        }
+        Ok(())
+    }
+
+    /// Bits of entropy of a password of random characters `length` long
+    fn password_entropy(&self, length: usize) -> f64 {
+        let pool_size: usize = self.classes().iter().map(|(pool, _)| pool.len()).sum();
+        if self.no_repeats {
+            (0..length)
+                .map(|i| (pool_size.saturating_sub(i).max(1) as f64).log2())
+                .sum()
+        } else {
+            length as f64 * (pool_size as f64).log2()
+        }
+    }
+
+    pub fn generate(&self) -> Result<GeneratedPassword, String> {
+        if self.mode == GeneratorMode::Passphrase {
+            return self.generate_passphrase();
+        }
+
+        self.validate()?;
+        let mut rng = rand::rng();
+        let mut classes = self.classes();
+        let length = rng.random_range(self.min_length..=self.max_length) as usize;
+        let mut password = Vec::with_capacity(length);
+
+        // Minimums first, then the rest from every class
+        for (pool, min) in &mut classes {
+            for _ in 0..*min {
+                password.push(pick(pool, self.no_repeats, &mut rng));
+            }
+        }
+        let mut everything: Vec<char> = classes.into_iter().flat_map(|(pool, _)| pool).collect();
+        while password.len() < length {
+            password.push(pick(&mut everything, self.no_repeats, &mut rng));
+        }
+        password.shuffle(&mut rng);
+
+        Ok(GeneratedPassword {
+            password: password.into_iter().collect(),
+            entropy: self.password_entropy(length),
+        })
+    }
+
+    fn generate_passphrase(&self) -> Result<GeneratedPassword, String> {
+        if self.words == 0 {
+            return Err(format!("Profile {} has no words", self.name));
+        }
+        let list = wordlist::load(&self.wordlist)?;
+        let mut rng = rand::rng();
+
+        let mut words: Vec<String> = (0..self.words)
+            .map(|_| list[rng.random_range(0..list.len())].clone())
+            .collect();
+        if self.capitalize {
+            for word in &mut words {
+                let mut chars = word.chars();
+                if let Some(first) = chars.next() {
+                    *word = first.to_uppercase().chain(chars).collect();
+                }
+            }
+        }
+        if self.digit {
+            let idx = rng.random_range(0..words.len());
+            let digit = rng.random_range(0..10);
+            words[idx].push_str(&digit.to_string());
+        }
+
+        Ok(GeneratedPassword {
+            password: words.join(&self.separator),
+            entropy: passphrase_entropy(self, list.len()),
+        })
+    }
+}
+
+impl GeneratorOverrides {
+    pub fn apply(&self, profile: &mut GeneratorProfile) {
+        if let Some(length) = self.length {
+            profile.min_length = length;
+            profile.max_length = length;
+        }
+        match self.symbols {
+            Some(false) => {
+                profile.symbols.clear();
+                profile.min_symbols = 0;
+            }
+            Some(true) if profile.symbols.is_empty() => {
+                profile.symbols = String::from(DEFAULT_SYMBOLS);
+            }
+            _ => {}
+        }
+        match self.passphrase {
+            Some(true) => profile.mode = GeneratorMode::Passphrase,
+            Some(false) => profile.mode = GeneratorMode::Password,
+            None => {}
+        }
+        if let Some(words) = self.words {
+            profile.words = words;
+        }
+        if let Some(separator) = &self.separator {
+            profile.separator = separator.clone();
+        }
+    }
+}
+
+/// Each word adds log2 of the list size, a digit adds its value and position
+fn passphrase_entropy(profile: &GeneratorProfile, list_size: usize) -> f64 {
+    let words = profile.words as f64;
+    let mut entropy = words * (list_size.max(1) as f64).log2();
+    if profile.digit {
+        entropy += 10f64.log2() + words.log2();
+    }
+    entropy
+}
+
+/// Pick a character, removing it from the pool if it can't be used again
+fn pick(pool: &mut Vec<char>, no_repeats: bool, rng: &mut ThreadRng) -> char {
+    let idx = rng.random_range(0..pool.len());
+    if no_repeats {
+        pool.swap_remove(idx)
+    } else {
+        pool[idx]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(password: &str, class: impl Fn(char) -> bool) -> usize {
+        password.chars().filter(|c| class(*c)).count()
+    }
+
+    #[test]
+    fn meets_each_class_minimum_within_the_length_range() {
+        let profile = GeneratorProfile {
+            min_length: 8,
+            max_length: 12,
+            min_upper: 3,
+            min_lower: 1,
+            min_digits: 2,
+            min_symbols: 2,
+            ..Default::default()
+        };
+        for _ in 0..200 {
+            let password = profile.generate().unwrap().password;
+            let length = password.chars().count();
+            assert!(
+                (8..=12).contains(&length),
+                "{} is {} long",
+                password,
+                length
+            );
+            assert!(count(&password, |c| c.is_ascii_uppercase()) >= 3);
+            assert!(count(&password, |c| c.is_ascii_lowercase()) >= 1);
+            assert!(count(&password, |c| c.is_ascii_digit()) >= 2);
+            assert!(count(&password, |c| DEFAULT_SYMBOLS.contains(c)) >= 2);
+        }
+    }
+
+    #[test]
+    fn keeps_to_the_pool_without_repeats() {
+        let profile = GeneratorProfile {
+            min_length: 40,
+            max_length: 40,
+            exclude_ambiguous: true,
+            no_repeats: true,
+            ..Default::default()
+        };
+        for _ in 0..50 {
+            let password = profile.generate().unwrap().password;
+            let mut chars: Vec<char> = password.chars().collect();
+            assert!(!chars.iter().any(|c| AMBIGUOUS.contains(*c)));
+            chars.sort();
+            chars.dedup();
+            assert_eq!(chars.len(), 40);
+        }
+    }
+
+    #[test]
+    fn rejects_a_zero_length_override() {
+        let mut profile = GeneratorProfile::default();
+        GeneratorOverrides {
+            length: Some(0),
+            ..Default::default()
+        }
+        .apply(&mut profile);
+        assert_eq!(
+            profile.generate().err().as_deref(),
+            Some("Profile default has an empty length range")
+        );
+    }
+
+    #[test]
+    fn rejects_minimums_longer_than_the_password() {
+        let profile = GeneratorProfile {
+            min_length: 4,
+            max_length: 10,
+            min_digits: 5,
+            ..Default::default()
+        };
+        assert!(profile.validate().is_err());
+    }
+
+    #[test]
+    fn reports_entropy_for_the_generated_length() {
+        // 26 + 26 + 10 + 32 characters
+        let pool_bits = 94f64.log2();
+        let profile = GeneratorProfile {
+            min_length: 10,
+            max_length: 30,
+            ..Default::default()
+        };
+        for _ in 0..100 {
+            let generated = profile.generate().unwrap();
+            let length = generated.password.chars().count() as f64;
+            assert!((generated.entropy - length * pool_bits).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn counts_a_shrinking_pool_without_repeats() {
+        let profile = GeneratorProfile {
+            min_length: 3,
+            max_length: 3,
+            upper: false,
+            lower: false,
+            symbols: String::new(),
+            no_repeats: true,
+            ..Default::default()
+        };
+        let generated = profile.generate().unwrap();
+        let expected = 10f64.log2() + 9f64.log2() + 8f64.log2();
+        assert!((generated.entropy - expected).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::app::find_credentials;
+use crate::config::HealthConfig;
+use crate::secret::{Pair, Secret};
+use crate::strength;
+use chrono::Local;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Critical,
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Clone)]
+pub enum IssueKind {
+    /// The password was seen this many times in the breach file
+    Breached(u64),
+    /// The password is also used by this many other entries
+    Reused(usize),
+    /// Estimated bits of entropy
+    Weak(f64),
+    /// Days since the entry was last changed
+    Old(i64),
+    MissingUsername,
+    MissingUrl,
+}
+
+/// Something wrong with one entry of the vault
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub secret_id: String,
+    pub secret_name: String,
+    pub severity: Severity,
+    pub kind: IssueKind,
+}
+
+impl Severity {
+    pub const ALL: [Severity; 3] = [Severity::Critical, Severity::Warning, Severity::Info];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Critical => "Critical",
+            Severity::Warning => "Warning",
+            Severity::Info => "Info",
+        }
+    }
+}
+
+impl Issue {
+    pub fn describe(&self) -> String {
+        match &self.kind {
+            IssueKind::Breached(count) => {
+                format!("Password seen {} times in data breaches", count)
+            }
+            IssueKind::Reused(1) => String::from("Password is shared with 1 other entry"),
+            IssueKind::Reused(others) => {
+                format!("Password is shared with {} other entries", others)
+            }
+            IssueKind::Weak(entropy) => format!("Weak password, about {:.0} bits", entropy),
+            IssueKind::Old(days) => format!("Not changed in {} days", days),
+            IssueKind::MissingUsername => String::from("No username"),
+            IssueKind::MissingUrl => String::from("No URL"),
+        }
+    }
+}
+
+fn is_url_key(key: &str) -> bool {
+    matches!(
+        key.to_lowercase().as_str(),
+        "url" | "uri" | "website" | "site" | "domain" | "link"
+    )
+}
+
+/// Audit every secret, most severe issues first. `breached` holds breach
+/// counts by secret ID, from `breach::find_breached`
+pub fn check(
+    secrets: &[Secret],
+    config: &HealthConfig,
+    breached: &HashMap<String, u64>,
+) -> Vec<Issue> {
+    let credentials: Vec<(Option<String>, Option<String>)> = secrets
+        .iter()
+        .map(|secret| find_credentials(&secret.get_contents()))
+        .collect();
+
+    let mut uses: HashMap<&str, usize> = HashMap::new();
+    for (_, password) in &credentials {
+        if let Some(password) = password.as_deref().filter(|p| !p.is_empty()) {
+            *uses.entry(password).or_default() += 1;
+        }
+    }
+
+    let now = Local::now();
+    let mut issues = Vec::new();
+    for (secret, (username, password)) in secrets.iter().zip(&credentials) {
+        let mut flag = |severity, kind| {
+            issues.push(Issue {
+                secret_id: secret.get_id().to_string(),
+                secret_name: secret.get_name().to_string(),
+                severity,
+                kind,
+            })
+        };
+
+        let age = (now - secret.get_last_modified()).num_days();
+        if age > config.max_password_age_days as i64 {
+            flag(Severity::Warning, IssueKind::Old(age));
+        }
+
+        // Notes and the like aren't logins, the rest only applies to those
+        let Some(password) = password.as_deref() else {
+            continue;
+        };
+        if let Some(count) = breached.get(secret.get_id()) {
+            flag(Severity::Critical, IssueKind::Breached(*count));
+        }
+        let others = uses.get(password).map_or(0, |count| count - 1);
+        if others > 0 {
+            flag(Severity::Critical, IssueKind::Reused(others));
+        }
+        let entropy = strength::estimate(password).entropy;
+        if entropy < config.weak_password_entropy {
+            flag(Severity::Critical, IssueKind::Weak(entropy));
+        }
+        if username.as_deref().is_none_or(str::is_empty) {
+            flag(Severity::Info, IssueKind::MissingUsername);
+        }
+        let contents = secret.get_contents();
+        let has_url = contents
+            .iter()
+            .any(|Pair { key, value }| is_url_key(key) && !value.is_empty());
+        if !has_url {
+            flag(Severity::Info, IssueKind::MissingUrl);
+        }
+    }
+
+    issues.sort_by(|a, b| {
+        a.severity.cmp(&b.severity).then_with(|| {
+            a.secret_name
+                .to_lowercase()
+                .cmp(&b.secret_name.to_lowercase())
+        })
+    });
+    issues
+}
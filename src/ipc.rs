@@ -1,23 +1,75 @@
+use crate::secret::Pair;
 use serde::{Deserialize, Serialize};
-use std::io::{Read, Write};
+use std::io::{self, BufRead, BufReader, Write};
 
-#[derive(Deserialize, Serialize)]
+/// Connections carry newline-delimited JSON in both directions and stay open,
+/// so a client can have several requests in flight and match the responses
+/// by `id`. After a `subscribe` request the connection also receives
+/// `AppEvent`s, e.g. `{"event":"locked"}`.
+#[derive(Default, Deserialize, Serialize)]
 pub struct IpcRequest {
+    /// Echoed back on the response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
     pub action: String,
     pub domain: Option<String>,
     pub username: Option<String>,
     pub password: Option<String>,
+    /// Generator profile for `generate_password`, the active one if unset
+    pub profile: Option<String>,
+    /// Overrides the profile's length for `generate_password`
+    pub length: Option<u8>,
+    /// Turns the profile's symbols off or on for `generate_password`
+    pub symbols: Option<bool>,
+    /// Generate words instead of characters, or the other way round
+    pub passphrase: Option<bool>,
+    /// Overrides the profile's word count for passphrases
+    pub words: Option<u8>,
+    /// Overrides the profile's word separator for passphrases
+    pub separator: Option<String>,
+    /// Queue the generated password in the inbox for `domain`
+    pub save: Option<bool>,
+    /// Text to match against entry names for `search`
+    pub query: Option<String>,
+    /// Entry to read for `get_entry` and `get_field`
+    pub entry_id: Option<String>,
+    /// Field to read for `get_field`
+    pub key: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct IpcResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
     pub ok: bool,
     pub username: Option<String>,
     pub password: Option<String>,
     pub message: Option<String>,
     pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entries: Option<Vec<IpcEntry>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry: Option<IpcEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// Bits of entropy of a generated password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entropy: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct IpcEntry {
+    pub id: String,
+    pub name: String,
+    pub fields: Vec<Pair>,
 }
 
+#[cfg(unix)]
+pub type IpcStream = std::os::unix::net::UnixStream;
+
+#[cfg(windows)]
+pub type IpcStream = std::fs::File;
+
 #[cfg(unix)]
 pub fn get_socket_path() -> String {
     "/tmp/grimoire.sock".to_string()
@@ -28,73 +80,51 @@ pub fn get_pipe_name() -> String {
     r"\\.\pipe\grimoire".to_string()
 }
 
-// Send request and receive response over IPC
 #[cfg(unix)]
-pub fn send_ipc_request(request: &IpcRequest) -> std::io::Result<IpcResponse> {
-    use std::os::unix::net::UnixStream;
-
-    let socket_path = get_socket_path();
-    let mut stream = UnixStream::connect(socket_path)?;
-
-    let request_json = serde_json::to_string(request)?;
-    stream.write_all(request_json.as_bytes())?;
-    stream.write_all(b"\n")?;
-    stream.flush()?;
-
-    let mut response_buffer = Vec::new();
-    let mut temp = [0u8; 1024];
-    loop {
-        match stream.read(&mut temp) {
-            Ok(0) => break,
-            Ok(n) => {
-                response_buffer.extend_from_slice(&temp[..n]);
-                if response_buffer.ends_with(&[b'\n']) {
-                    break;
-                }
-            }
-            Err(e) => return Err(e),
-        }
-    }
-
-    if response_buffer.ends_with(&[b'\n']) {
-        response_buffer.pop();
-    }
-
-    serde_json::from_slice(&response_buffer)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+pub fn connect() -> io::Result<IpcStream> {
+    IpcStream::connect(get_socket_path())
 }
 
 #[cfg(windows)]
-pub fn send_ipc_request(request: &IpcRequest) -> std::io::Result<IpcResponse> {
-    use std::fs::OpenOptions;
-
-    let pipe_name = get_pipe_name();
-    let mut stream = OpenOptions::new().read(true).write(true).open(pipe_name)?;
+pub fn connect() -> io::Result<IpcStream> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(get_pipe_name())
+}
 
-    let request_json = serde_json::to_string(request)?;
-    stream.write_all(request_json.as_bytes())?;
-    stream.write_all(b"\n")?;
-    stream.flush()?;
+/// Write one message as a line of JSON
+pub fn write_message<W: Write, T: Serialize>(writer: &mut W, message: &T) -> io::Result<()> {
+    let json = serde_json::to_string(message)?;
+    writer.write_all(json.as_bytes())?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
 
-    let mut response_buffer = Vec::new();
-    let mut temp = [0u8; 1024];
-    loop {
-        match stream.read(&mut temp) {
-            Ok(0) => break,
-            Ok(n) => {
-                response_buffer.extend_from_slice(&temp[..n]);
-                if response_buffer.ends_with(&[b'\n']) {
-                    break;
-                }
-            }
-            Err(e) => return Err(e),
-        }
+/// Read one line, without its newline. None once the other side hangs up
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
     }
-
-    if response_buffer.ends_with(&[b'\n']) {
-        response_buffer.pop();
+    if line.ends_with(b"\n") {
+        line.pop();
     }
+    Ok(Some(line))
+}
 
-    serde_json::from_slice(&response_buffer)
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+// Send a single request and receive its response over a fresh connection
+pub fn send_ipc_request(request: &IpcRequest) -> io::Result<IpcResponse> {
+    let mut stream = connect()?;
+    write_message(&mut stream, request)?;
+
+    let mut reader = BufReader::new(stream);
+    match read_message(&mut reader)? {
+        Some(response) => serde_json::from_slice(&response)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        None => Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Grimoire closed the connection",
+        )),
+    }
 }
@@ -0,0 +1,76 @@
+use argon2::{
+    Algorithm, Argon2, Params, Version,
+    password_hash::{self, PasswordHash},
+};
+
+/// How much work goes into hashing the master password. Slower profiles
+/// make guessing it from a stolen hash file slower too
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum KdfProfile {
+    #[default]
+    Interactive,
+    Moderate,
+    Sensitive,
+}
+
+impl KdfProfile {
+    pub const ALL: [KdfProfile; 3] = [
+        KdfProfile::Interactive,
+        KdfProfile::Moderate,
+        KdfProfile::Sensitive,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KdfProfile::Interactive => "interactive",
+            KdfProfile::Moderate => "moderate",
+            KdfProfile::Sensitive => "sensitive",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            KdfProfile::Interactive => "19 MiB, 2 passes. Unlocks instantly, fine for most",
+            KdfProfile::Moderate => "64 MiB, 3 passes. About a second to unlock",
+            KdfProfile::Sensitive => "256 MiB, 4 passes. Several seconds, for fast machines",
+        }
+    }
+
+    /// Memory in KiB, passes and lanes
+    fn costs(&self) -> (u32, u32, u32) {
+        match self {
+            KdfProfile::Interactive => (Params::DEFAULT_M_COST, Params::DEFAULT_T_COST, 1),
+            KdfProfile::Moderate => (64 * 1024, 3, 4),
+            KdfProfile::Sensitive => (256 * 1024, 4, 4),
+        }
+    }
+
+    pub fn params(&self) -> Params {
+        let (m_cost, t_cost, p_cost) = self.costs();
+        Params::new(m_cost, t_cost, p_cost, None).expect("profile costs are in range")
+    }
+
+    pub fn hasher(&self) -> Argon2<'static> {
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params())
+    }
+}
+
+/// The hasher a stored hash was made with, so the vault key is derived
+/// with the same costs
+pub fn hasher_for(hash: &PasswordHash) -> password_hash::Result<Argon2<'static>> {
+    let algorithm = Algorithm::try_from(hash.algorithm)?;
+    let version = match hash.version {
+        Some(version) => Version::try_from(version)?,
+        None => Version::default(),
+    };
+    Ok(Argon2::new(algorithm, version, Params::try_from(hash)?))
+}
+
+/// The profile a stored hash was made with, if it was one of them
+pub fn profile_for(hash: &PasswordHash) -> Option<KdfProfile> {
+    let params = Params::try_from(hash).ok()?;
+    let costs = (params.m_cost(), params.t_cost(), params.p_cost());
+    KdfProfile::ALL
+        .into_iter()
+        .find(|profile| profile.costs() == costs)
+}
@@ -0,0 +1,971 @@
+use crate::line_editor::EDIT_KEYS;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Everything a key can do. The first nine can be rebound in the
+/// `[keymap]` config section, the rest have fixed keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    New,
+    Delete,
+    Search,
+    Lock,
+    /// Show sensitive values on cards and in the detail pane
+    Reveal,
+    /// Put a generated password in the field being edited
+    Generate,
+    /// Copy the field being edited
+    Copy,
+    /// Paste into the field being edited
+    Paste,
+    Help,
+    /// Confirm, or go into whatever is selected
+    Submit,
+    /// Close, cancel or step back out
+    Back,
+    /// Move the selection with the arrow keys
+    Move,
+    Sidebar,
+    Favorite,
+    Tags,
+    Folder,
+    Sort,
+    View,
+    Jump,
+    Inbox,
+    Health,
+    Trash,
+    Save,
+    NextField,
+    PreviousField,
+    DeletePair,
+    RestorePair,
+    CycleProfile,
+    FindNext,
+    Accept,
+    Merge,
+    Discard,
+    Restore,
+    Purge,
+    /// Switch the Login screen to the recovery kit's code and back
+    Recover,
+    /// Handled by the line editor
+    EditText,
+}
+
+impl Action {
+    /// The actions that can be rebound
+    pub const CONFIGURABLE: [Action; 9] = [
+        Action::Quit,
+        Action::New,
+        Action::Delete,
+        Action::Search,
+        Action::Lock,
+        Action::Reveal,
+        Action::Generate,
+        Action::Copy,
+        Action::Paste,
+    ];
+
+    /// The name used in the config file and in errors
+    pub fn key(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::New => "new",
+            Action::Delete => "delete",
+            Action::Search => "search",
+            Action::Lock => "lock",
+            Action::Reveal => "reveal",
+            Action::Generate => "generate",
+            Action::Copy => "copy",
+            Action::Paste => "paste",
+            Action::Help => "help",
+            Action::Submit => "submit",
+            Action::Back => "back",
+            Action::Move => "move",
+            Action::Sidebar => "sidebar",
+            Action::Favorite => "favorite",
+            Action::Tags => "tags",
+            Action::Folder => "folder",
+            Action::Sort => "sort",
+            Action::View => "view",
+            Action::Jump => "jump",
+            Action::Inbox => "inbox",
+            Action::Health => "health",
+            Action::Trash => "trash",
+            Action::Save => "save",
+            Action::NextField => "next field",
+            Action::PreviousField => "previous field",
+            Action::DeletePair => "delete entry",
+            Action::RestorePair => "restore entry",
+            Action::CycleProfile => "generator profile",
+            Action::FindNext => "find next",
+            Action::Accept => "accept",
+            Action::Merge => "merge",
+            Action::Discard => "discard",
+            Action::Restore => "restore",
+            Action::Purge => "purge",
+            Action::Recover => "recover",
+            Action::EditText => "text editing",
+        }
+    }
+
+    fn configurable(&self) -> bool {
+        Action::CONFIGURABLE.contains(self)
+    }
+}
+
+/// Where a key is pressed, which decides what it does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    Login,
+    Init,
+    /// The Init steps chosen from a list
+    InitChoice,
+    Main,
+    /// The filter sidebar has focus; other keys work as on the main screen
+    Sidebar,
+    Search,
+    Jump,
+    /// Typing tags or a folder
+    Prompt,
+    Editor,
+    /// Asked whether to throw away unsaved edits
+    Discard,
+    ConfirmDelete,
+    Inbox,
+    /// Choosing the secret to merge a captured credential into
+    Merge,
+    Health,
+    Trash,
+    ConfirmPurge,
+}
+
+impl Context {
+    const ALL: [Context; 16] = [
+        Context::Login,
+        Context::Init,
+        Context::InitChoice,
+        Context::Main,
+        Context::Sidebar,
+        Context::Search,
+        Context::Jump,
+        Context::Prompt,
+        Context::Editor,
+        Context::Discard,
+        Context::ConfirmDelete,
+        Context::Inbox,
+        Context::Merge,
+        Context::Health,
+        Context::Trash,
+        Context::ConfirmPurge,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Context::Login => "Unlocking",
+            Context::Init | Context::InitChoice => "Setting up",
+            Context::Main => "Secrets",
+            Context::Sidebar => "Filters",
+            Context::Search => "Searching",
+            Context::Jump => "Jumping",
+            Context::Prompt => "Tags and folders",
+            Context::Editor => "Editing",
+            Context::Discard => "Unsaved changes",
+            Context::ConfirmDelete => "Deleting",
+            Context::Inbox => "Inbox",
+            Context::Merge => "Merging",
+            Context::Health => "Vault health",
+            Context::Trash => "Trash",
+            Context::ConfirmPurge => "Deleting for good",
+        }
+    }
+
+    /// Whether keys that aren't bound are typed into a text field
+    pub fn typing(&self) -> bool {
+        matches!(
+            self,
+            Context::Login
+                | Context::Init
+                | Context::Search
+                | Context::Jump
+                | Context::Prompt
+                | Context::Editor
+        )
+    }
+
+    /// Where to look for keys this context doesn't bind itself
+    fn fallback(&self) -> Option<Context> {
+        match self {
+            Context::Sidebar => Some(Context::Main),
+            _ => None,
+        }
+    }
+}
+
+/// A key with the Ctrl and Alt modifiers held with it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> KeyChord {
+        KeyChord { code, modifiers }
+    }
+
+    const fn plain(c: char) -> KeyChord {
+        KeyChord::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    pub const fn ctrl(c: char) -> KeyChord {
+        KeyChord::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    const fn alt(c: char) -> KeyChord {
+        KeyChord::new(KeyCode::Char(c), KeyModifiers::ALT)
+    }
+
+    pub const fn key(code: KeyCode) -> KeyChord {
+        KeyChord::new(code, KeyModifiers::NONE)
+    }
+
+    /// Read chords like "q", "ctrl+g", "alt+shift+x", "delete" or "pagedown"
+    pub fn parse(text: &str) -> Result<KeyChord, String> {
+        let (mods, key) = match text.strip_suffix("++") {
+            Some(mods) => (mods, "+"),
+            None => match text.rsplit_once('+') {
+                Some((mods, key)) => (mods, key),
+                None => ("", text),
+            },
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        let mut shift = false;
+        for modifier in mods.split('+').filter(|m| !m.is_empty()) {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => modifiers |= KeyModifiers::CONTROL,
+                "alt" | "meta" => modifiers |= KeyModifiers::ALT,
+                "shift" => shift = true,
+                _ => return Err(format!("Unknown modifier {} in {:?}", modifier, text)),
+            }
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) if shift => KeyCode::Char(c.to_ascii_uppercase()),
+            // Terminals report Ctrl and Alt with lowercase letters
+            (Some(c), None) if !modifiers.is_empty() => KeyCode::Char(c.to_ascii_lowercase()),
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_lowercase().as_str() {
+                "tab" if shift => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "esc" | "escape" => KeyCode::Esc,
+                "enter" | "return" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(format!("Unknown key {:?}", text)),
+                },
+            },
+        };
+        Ok(KeyChord::new(code, modifiers))
+    }
+
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        // Shift is already part of the character typed
+        let held = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        self.code == key.code && self.modifiers == held
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) if !self.modifiers.is_empty() => {
+                write!(f, "{}", c.to_ascii_uppercase())
+            }
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::BackTab => write!(f, "Shift+Tab"),
+            KeyCode::Delete => write!(f, "Del"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+const ESC: &[KeyChord] = &[KeyChord::key(KeyCode::Esc)];
+const ENTER: &[KeyChord] = &[KeyChord::key(KeyCode::Enter)];
+const UP_DOWN: &[KeyChord] = &[KeyChord::key(KeyCode::Up), KeyChord::key(KeyCode::Down)];
+const HELP: &[KeyChord] = &[KeyChord::plain('?'), KeyChord::key(KeyCode::F(1))];
+/// Help while typing, where '?' is just a character
+const TYPING_HELP: &[KeyChord] = &[KeyChord::key(KeyCode::F(1))];
+/// Left empty in [`BINDINGS`] for actions the keymap preset decides
+const CONFIGURED: &[KeyChord] = &[];
+
+/// Every key `run_app` dispatches, and what the help overlay says about it
+const BINDINGS: &[(Context, Action, &[KeyChord], &str)] = &[
+    (Context::Login, Action::Submit, ENTER, "unlock the vault"),
+    (Context::Login, Action::Back, ESC, "quit"),
+    (
+        Context::Login,
+        Action::Recover,
+        &[KeyChord::ctrl('r')],
+        "use the recovery kit's code, or go back",
+    ),
+    (Context::Login, Action::Help, TYPING_HELP, "show these keys"),
+    (Context::Init, Action::Submit, ENTER, "continue"),
+    (Context::Init, Action::Back, ESC, "go back a step, or quit"),
+    (Context::Init, Action::Help, TYPING_HELP, "show these keys"),
+    (Context::InitChoice, Action::Move, UP_DOWN, "choose"),
+    (Context::InitChoice, Action::Submit, ENTER, "continue"),
+    (Context::InitChoice, Action::Back, ESC, "go back a step"),
+    (Context::InitChoice, Action::Help, HELP, "show these keys"),
+    (
+        Context::Main,
+        Action::Move,
+        &[
+            KeyChord::key(KeyCode::Left),
+            KeyChord::key(KeyCode::Right),
+            KeyChord::key(KeyCode::Up),
+            KeyChord::key(KeyCode::Down),
+        ],
+        "move between secrets",
+    ),
+    (
+        Context::Main,
+        Action::Move,
+        &[
+            KeyChord::key(KeyCode::PageUp),
+            KeyChord::key(KeyCode::PageDown),
+            KeyChord::key(KeyCode::Home),
+            KeyChord::key(KeyCode::End),
+        ],
+        "a page at a time, or to either end",
+    ),
+    (
+        Context::Main,
+        Action::Submit,
+        ENTER,
+        "edit the selected secret",
+    ),
+    (Context::Main, Action::New, CONFIGURED, "make a new secret"),
+    (
+        Context::Main,
+        Action::Delete,
+        CONFIGURED,
+        "move the selected secret to the trash",
+    ),
+    (Context::Main, Action::Search, CONFIGURED, "search"),
+    (
+        Context::Main,
+        Action::Jump,
+        &[KeyChord::plain('j')],
+        "jump to a secret by typing its name",
+    ),
+    (
+        Context::Main,
+        Action::Favorite,
+        &[KeyChord::plain('*')],
+        "mark or unmark as a favorite",
+    ),
+    (
+        Context::Main,
+        Action::Tags,
+        &[KeyChord::plain('t')],
+        "edit tags",
+    ),
+    (
+        Context::Main,
+        Action::Folder,
+        &[KeyChord::plain('f')],
+        "move to a folder",
+    ),
+    (
+        Context::Main,
+        Action::Sidebar,
+        &[KeyChord::key(KeyCode::Tab)],
+        "filter by folder or tag",
+    ),
+    (
+        Context::Main,
+        Action::Sort,
+        &[KeyChord::plain('s')],
+        "change the sort order",
+    ),
+    (
+        Context::Main,
+        Action::View,
+        &[KeyChord::plain('v')],
+        "switch between grid and list",
+    ),
+    (
+        Context::Main,
+        Action::Reveal,
+        CONFIGURED,
+        "show or hide sensitive values",
+    ),
+    (
+        Context::Main,
+        Action::Inbox,
+        &[KeyChord::plain('i')],
+        "review captured credentials",
+    ),
+    (
+        Context::Main,
+        Action::Health,
+        &[KeyChord::plain('h')],
+        "vault health report",
+    ),
+    (
+        Context::Main,
+        Action::Trash,
+        &[KeyChord::plain('b')],
+        "open the trash",
+    ),
+    (Context::Main, Action::Lock, CONFIGURED, "lock the vault"),
+    (
+        Context::Main,
+        Action::Back,
+        ESC,
+        "clear the selection, or quit",
+    ),
+    (Context::Main, Action::Quit, CONFIGURED, "quit"),
+    (Context::Main, Action::Help, HELP, "show these keys"),
+    (Context::Sidebar, Action::Move, UP_DOWN, "choose a filter"),
+    (
+        Context::Sidebar,
+        Action::Back,
+        &[
+            KeyChord::key(KeyCode::Tab),
+            KeyChord::key(KeyCode::Enter),
+            KeyChord::key(KeyCode::Esc),
+        ],
+        "back to the secrets",
+    ),
+    (
+        Context::Search,
+        Action::FindNext,
+        &[KeyChord::key(KeyCode::Tab)],
+        "find the next match",
+    ),
+    (Context::Search, Action::Submit, ENTER, "edit the match"),
+    (Context::Search, Action::Paste, CONFIGURED, "paste"),
+    (Context::Search, Action::Back, ESC, "stop searching"),
+    (
+        Context::Search,
+        Action::Help,
+        TYPING_HELP,
+        "show these keys",
+    ),
+    (
+        Context::Jump,
+        Action::Back,
+        &[KeyChord::key(KeyCode::Enter), KeyChord::key(KeyCode::Esc)],
+        "stop jumping",
+    ),
+    (Context::Jump, Action::Help, TYPING_HELP, "show these keys"),
+    (Context::Prompt, Action::Submit, ENTER, "save"),
+    (Context::Prompt, Action::Paste, CONFIGURED, "paste"),
+    (Context::Prompt, Action::Back, ESC, "cancel"),
+    (
+        Context::Prompt,
+        Action::Help,
+        TYPING_HELP,
+        "show these keys",
+    ),
+    (
+        Context::Editor,
+        Action::NextField,
+        &[KeyChord::key(KeyCode::Tab)],
+        "next field",
+    ),
+    (
+        Context::Editor,
+        Action::PreviousField,
+        &[KeyChord::key(KeyCode::BackTab)],
+        "previous field",
+    ),
+    (Context::Editor, Action::Move, UP_DOWN, "choose an entry"),
+    (Context::Editor, Action::Submit, ENTER, "add the entry"),
+    (
+        Context::Editor,
+        Action::DeletePair,
+        &[KeyChord::ctrl('d')],
+        "delete the entry",
+    ),
+    (
+        Context::Editor,
+        Action::RestorePair,
+        &[KeyChord::ctrl('r')],
+        "bring back a deleted entry",
+    ),
+    (
+        Context::Editor,
+        Action::Generate,
+        CONFIGURED,
+        "generate a password",
+    ),
+    (
+        Context::Editor,
+        Action::CycleProfile,
+        &[KeyChord::ctrl('p')],
+        "next generator profile",
+    ),
+    (Context::Editor, Action::Copy, CONFIGURED, "copy the field"),
+    (Context::Editor, Action::Paste, CONFIGURED, "paste"),
+    (
+        Context::Editor,
+        Action::Save,
+        &[KeyChord::ctrl('s')],
+        "save and close",
+    ),
+    (Context::Editor, Action::Back, ESC, "close"),
+    (
+        Context::Editor,
+        Action::Help,
+        TYPING_HELP,
+        "show these keys",
+    ),
+    (
+        Context::Discard,
+        Action::Discard,
+        &[KeyChord::plain('y')],
+        "throw the changes away",
+    ),
+    (
+        Context::Discard,
+        Action::Save,
+        &[KeyChord::plain('s')],
+        "save them",
+    ),
+    (
+        Context::Discard,
+        Action::Back,
+        &[KeyChord::plain('n'), KeyChord::key(KeyCode::Esc)],
+        "keep editing",
+    ),
+    (
+        Context::ConfirmDelete,
+        Action::Submit,
+        &[KeyChord::plain('y'), KeyChord::key(KeyCode::Enter)],
+        "move it to the trash",
+    ),
+    (
+        Context::ConfirmDelete,
+        Action::Back,
+        &[KeyChord::plain('n'), KeyChord::key(KeyCode::Esc)],
+        "keep it",
+    ),
+    (Context::Inbox, Action::Move, UP_DOWN, "choose a credential"),
+    (
+        Context::Inbox,
+        Action::Accept,
+        &[KeyChord::plain('a')],
+        "accept it as a new secret",
+    ),
+    (
+        Context::Inbox,
+        Action::Merge,
+        &[KeyChord::plain('m')],
+        "merge it into a secret",
+    ),
+    (
+        Context::Inbox,
+        Action::Discard,
+        &[KeyChord::plain('d'), KeyChord::key(KeyCode::Delete)],
+        "discard it",
+    ),
+    (Context::Inbox, Action::Back, ESC, "close"),
+    (Context::Inbox, Action::Help, HELP, "show these keys"),
+    (Context::Merge, Action::Move, UP_DOWN, "choose a secret"),
+    (
+        Context::Merge,
+        Action::Submit,
+        ENTER,
+        "replace its username and password",
+    ),
+    (Context::Merge, Action::Back, ESC, "back to the inbox"),
+    (Context::Merge, Action::Help, HELP, "show these keys"),
+    (Context::Health, Action::Move, UP_DOWN, "choose an issue"),
+    (Context::Health, Action::Submit, ENTER, "open the entry"),
+    (Context::Health, Action::Back, ESC, "close"),
+    (Context::Health, Action::Help, HELP, "show these keys"),
+    (Context::Trash, Action::Move, UP_DOWN, "choose a secret"),
+    (
+        Context::Trash,
+        Action::Restore,
+        &[KeyChord::plain('r'), KeyChord::key(KeyCode::Enter)],
+        "restore it",
+    ),
+    (
+        Context::Trash,
+        Action::Purge,
+        &[KeyChord::plain('p'), KeyChord::key(KeyCode::Delete)],
+        "delete it for good",
+    ),
+    (Context::Trash, Action::Back, ESC, "close"),
+    (
+        Context::ConfirmPurge,
+        Action::Submit,
+        &[KeyChord::plain('y'), KeyChord::key(KeyCode::Enter)],
+        "delete it for good",
+    ),
+    (
+        Context::ConfirmPurge,
+        Action::Back,
+        &[KeyChord::plain('n'), KeyChord::key(KeyCode::Esc)],
+        "keep it",
+    ),
+    (Context::Trash, Action::Help, HELP, "show these keys"),
+];
+
+/// Ready-made sets of bindings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeymapPreset {
+    #[default]
+    Default,
+    Vim,
+    Emacs,
+}
+
+/// One chord or several for the same action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Chords {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// The `[keymap]` config section: a preset, and chords for any actions
+/// that should differ from it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeymapConfig {
+    pub preset: KeymapPreset,
+    #[serde(flatten)]
+    pub bindings: BTreeMap<String, Chords>,
+}
+
+impl KeymapConfig {
+    /// Parse and check the bindings, so mistakes are found at startup
+    pub fn build(&self) -> Result<Keymap, String> {
+        let mut keymap = Keymap::preset(self.preset);
+        for (name, chords) in &self.bindings {
+            let action = Action::CONFIGURABLE
+                .into_iter()
+                .find(|action| action.key() == name)
+                .ok_or(format!("Unknown action {} in [keymap]", name))?;
+            let chords = match chords {
+                Chords::One(chord) => vec![KeyChord::parse(chord)?],
+                Chords::Many(chords) => chords
+                    .iter()
+                    .map(|chord| KeyChord::parse(chord))
+                    .collect::<Result<_, _>>()?,
+            };
+            keymap.set(action, chords);
+        }
+        keymap.validate()?;
+        Ok(keymap)
+    }
+}
+
+/// An action, where it applies and the chords that trigger it
+#[derive(Debug, Clone)]
+struct Binding {
+    context: Context,
+    action: Action,
+    chords: Vec<KeyChord>,
+    description: &'static str,
+}
+
+/// The chords bound to each action
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        Keymap::preset(KeymapPreset::Default)
+    }
+}
+
+impl Keymap {
+    pub fn preset(preset: KeymapPreset) -> Keymap {
+        let configured = |action| -> Vec<KeyChord> {
+            match (preset, action) {
+                (KeymapPreset::Default, Action::Quit) => vec![KeyChord::plain('q')],
+                (KeymapPreset::Default, Action::New) => vec![KeyChord::plain('n')],
+                (KeymapPreset::Default, Action::Delete) => {
+                    vec![KeyChord::plain('x'), KeyChord::key(KeyCode::Delete)]
+                }
+                (KeymapPreset::Default, Action::Search) => vec![KeyChord::plain('/')],
+                (KeymapPreset::Default, Action::Lock) => vec![KeyChord::plain('l')],
+                (KeymapPreset::Default, Action::Reveal) => vec![KeyChord::plain('r')],
+                (KeymapPreset::Default, Action::Generate) => vec![KeyChord::ctrl('g')],
+                (KeymapPreset::Default, Action::Copy) => vec![KeyChord::ctrl('c')],
+                (KeymapPreset::Default, Action::Paste) => vec![KeyChord::ctrl('v')],
+
+                (KeymapPreset::Vim, Action::Quit) => vec![KeyChord::plain('q')],
+                (KeymapPreset::Vim, Action::New) => vec![KeyChord::plain('o')],
+                (KeymapPreset::Vim, Action::Delete) => {
+                    vec![KeyChord::plain('d'), KeyChord::plain('x')]
+                }
+                (KeymapPreset::Vim, Action::Search) => vec![KeyChord::plain('/')],
+                (KeymapPreset::Vim, Action::Lock) => vec![KeyChord::plain('L')],
+                (KeymapPreset::Vim, Action::Reveal) => vec![KeyChord::plain('z')],
+                (KeymapPreset::Vim, Action::Generate) => vec![KeyChord::ctrl('g')],
+                (KeymapPreset::Vim, Action::Copy) => vec![KeyChord::alt('y')],
+                (KeymapPreset::Vim, Action::Paste) => vec![KeyChord::alt('p')],
+
+                (KeymapPreset::Emacs, Action::Quit) => vec![KeyChord::ctrl('q')],
+                (KeymapPreset::Emacs, Action::New) => vec![KeyChord::ctrl('o')],
+                (KeymapPreset::Emacs, Action::Delete) => vec![KeyChord::ctrl('k')],
+                (KeymapPreset::Emacs, Action::Search) => vec![KeyChord::ctrl('s')],
+                (KeymapPreset::Emacs, Action::Lock) => vec![KeyChord::ctrl('l')],
+                (KeymapPreset::Emacs, Action::Reveal) => vec![KeyChord::alt('r')],
+                (KeymapPreset::Emacs, Action::Generate) => vec![KeyChord::alt('g')],
+                (KeymapPreset::Emacs, Action::Copy) => vec![KeyChord::alt('w')],
+                (KeymapPreset::Emacs, Action::Paste) => vec![KeyChord::alt('y')],
+                _ => vec![],
+            }
+        };
+        let mut bindings: Vec<Binding> = BINDINGS
+            .iter()
+            .map(|&(context, action, chords, description)| Binding {
+                context,
+                action,
+                chords: if chords.is_empty() {
+                    configured(action)
+                } else {
+                    chords.to_vec()
+                },
+                description,
+            })
+            .collect();
+        for context in Context::ALL.into_iter().filter(Context::typing) {
+            bindings.extend(EDIT_KEYS.iter().map(|&(keys, description)| Binding {
+                context,
+                action: Action::EditText,
+                chords: keys.iter().map(|&(chord, _)| chord).collect(),
+                description,
+            }));
+        }
+        Keymap { bindings }
+    }
+
+    /// What the key does in the context, if anything
+    pub fn action(&self, context: Context, key: &KeyEvent) -> Option<Action> {
+        let found = self
+            .bindings
+            .iter()
+            .find(|b| b.context == context && b.chords.iter().any(|chord| chord.matches(key)));
+        match found {
+            Some(binding) => Some(binding.action),
+            None => context
+                .fallback()
+                .and_then(|fallback| self.action(fallback, key)),
+        }
+    }
+
+    /// The keys that do something in the context and what they do, for
+    /// the help overlay
+    pub fn help(&self, context: Context) -> Vec<(String, &'static str)> {
+        let mut shadowed: Vec<KeyChord> = vec![];
+        let mut rows = vec![];
+        let mut current = Some(context);
+        while let Some(context) = current {
+            for binding in self.bindings.iter().filter(|b| b.context == context) {
+                let chords: Vec<String> = binding
+                    .chords
+                    .iter()
+                    .filter(|chord| !shadowed.contains(chord))
+                    .map(KeyChord::to_string)
+                    .collect();
+                if !chords.is_empty() {
+                    rows.push((chords.join(" / "), binding.description));
+                }
+            }
+            shadowed.extend(
+                self.bindings
+                    .iter()
+                    .filter(|b| b.context == context)
+                    .flat_map(|b| b.chords.iter().copied()),
+            );
+            current = context.fallback();
+        }
+        rows
+    }
+
+    /// The first chord bound to the action, for hints
+    pub fn hint(&self, action: Action) -> String {
+        match self.chords(action).first() {
+            Some(chord) => chord.to_string(),
+            None => String::from("unbound"),
+        }
+    }
+
+    fn chords(&self, action: Action) -> &[KeyChord] {
+        self.bindings
+            .iter()
+            .find(|binding| binding.action == action)
+            .map_or(&[], |binding| binding.chords.as_slice())
+    }
+
+    fn set(&mut self, action: Action, chords: Vec<KeyChord>) {
+        for binding in self.bindings.iter_mut().filter(|b| b.action == action) {
+            binding.chords = chords.clone();
+        }
+    }
+
+    /// Refuse chords that do two things in the same place, or that would
+    /// get in the way of typing
+    fn validate(&self) -> Result<(), String> {
+        for (i, binding) in self.bindings.iter().enumerate() {
+            for chord in &binding.chords {
+                if binding.action.configurable()
+                    && binding.context.typing()
+                    && chord.modifiers.is_empty()
+                {
+                    return Err(format!(
+                        "{} needs Ctrl or Alt, {} would be typed instead",
+                        binding.action.key(),
+                        chord
+                    ));
+                }
+                let clash = self.bindings[i + 1..]
+                    .iter()
+                    .find(|other| other.context == binding.context && other.chords.contains(chord));
+                if let Some(other) = clash {
+                    return Err(format!(
+                        "{} and {} are both bound to {}",
+                        binding.action.key(),
+                        other.action.key(),
+                        chord
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(toml: &str) -> Result<Keymap, String> {
+        toml::from_str::<KeymapConfig>(toml).unwrap().build()
+    }
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn every_preset_is_valid() {
+        for preset in ["default", "vim", "emacs"] {
+            build(&format!("preset = {:?}", preset)).unwrap();
+        }
+    }
+
+    #[test]
+    fn parses_chords() {
+        let chord = |text| KeyChord::parse(text).unwrap();
+        assert_eq!(chord("q"), KeyChord::plain('q'));
+        assert_eq!(chord("ctrl+G"), KeyChord::ctrl('g'));
+        assert_eq!(
+            chord("alt+shift+x"),
+            KeyChord::new(KeyCode::Char('X'), KeyModifiers::ALT)
+        );
+        assert_eq!(chord("ctrl++"), KeyChord::ctrl('+'));
+        assert_eq!(chord("shift+tab"), KeyChord::key(KeyCode::BackTab));
+        assert_eq!(chord("PageDown"), KeyChord::key(KeyCode::PageDown));
+        assert_eq!(chord("f12"), KeyChord::key(KeyCode::F(12)));
+    }
+
+    #[test]
+    fn binds_a_configured_chord_in_place_of_the_preset() {
+        let keymap = build("quit = \"ctrl+x\"\ndelete = [\"d\", \"delete\"]").unwrap();
+        let ctrl_x = press(KeyCode::Char('x'), KeyModifiers::CONTROL);
+        let q = press(KeyCode::Char('q'), KeyModifiers::NONE);
+        let d = press(KeyCode::Char('d'), KeyModifiers::NONE);
+        let delete = press(KeyCode::Delete, KeyModifiers::NONE);
+        assert_eq!(keymap.action(Context::Main, &ctrl_x), Some(Action::Quit));
+        assert_eq!(keymap.action(Context::Main, &q), None);
+        assert_eq!(keymap.action(Context::Main, &d), Some(Action::Delete));
+        assert_eq!(keymap.action(Context::Main, &delete), Some(Action::Delete));
+    }
+
+    #[test]
+    fn rejects_an_unknown_key() {
+        assert_eq!(
+            build("quit = \"ctrl+f13\"").err().as_deref(),
+            Some("Unknown key \"ctrl+f13\"")
+        );
+        assert_eq!(
+            build("quit = \"hyper+q\"").err().as_deref(),
+            Some("Unknown modifier hyper in \"hyper+q\"")
+        );
+    }
+
+    #[test]
+    fn rejects_an_unknown_action() {
+        assert_eq!(
+            build("explode = \"e\"").err().as_deref(),
+            Some("Unknown action explode in [keymap]")
+        );
+        // fixed actions can't be rebound either
+        assert!(build("submit = \"ctrl+m\"").is_err());
+    }
+
+    #[test]
+    fn reports_two_actions_on_one_chord_in_a_context() {
+        assert_eq!(
+            build("new = \"q\"").err().as_deref(),
+            Some("new and quit are both bound to q")
+        );
+    }
+
+    #[test]
+    fn allows_one_chord_in_different_contexts() {
+        // w quits from the main screen while the editor still types it
+        let keymap = build("quit = \"w\"").unwrap();
+        let w = press(KeyCode::Char('w'), KeyModifiers::NONE);
+        assert_eq!(keymap.action(Context::Main, &w), Some(Action::Quit));
+        assert_eq!(keymap.action(Context::Editor, &w), None);
+    }
+
+    #[test]
+    fn keeps_plain_keys_out_of_typing_contexts() {
+        assert_eq!(
+            build("paste = \"p\"").err().as_deref(),
+            Some("paste needs Ctrl or Alt, p would be typed instead")
+        );
+    }
+}
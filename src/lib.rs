@@ -1,5 +1,17 @@
 pub mod app;
+pub mod breach;
 pub mod config;
+pub mod generator;
+pub mod health;
 pub mod ipc;
+pub mod kdf;
+pub mod keymap;
+pub mod line_editor;
+pub mod lockout;
+pub mod native_host;
+pub mod recovery;
 pub mod secret;
+pub mod strength;
+pub mod theme;
 pub mod ui;
+pub mod wordlist;
@@ -0,0 +1,446 @@
+use crate::keymap::KeyChord;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Most undo steps kept per field
+const MAX_UNDO: usize = 100;
+
+/// What an editing key does to the text or cursor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextEdit {
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    Home,
+    End,
+    Backspace,
+    Delete,
+    DeleteWord,
+    DeleteToStart,
+    Undo,
+    Redo,
+}
+
+/// The keys `handle_key` acts on, in rows as the key help lists them
+pub const EDIT_KEYS: &[(&[(KeyChord, TextEdit)], &str)] = &[
+    (
+        &[
+            (KeyChord::key(KeyCode::Left), TextEdit::Left),
+            (KeyChord::key(KeyCode::Right), TextEdit::Right),
+        ],
+        "move the cursor",
+    ),
+    (
+        &[
+            (
+                KeyChord::new(KeyCode::Left, KeyModifiers::CONTROL),
+                TextEdit::WordLeft,
+            ),
+            (
+                KeyChord::new(KeyCode::Right, KeyModifiers::CONTROL),
+                TextEdit::WordRight,
+            ),
+        ],
+        "move by word",
+    ),
+    (
+        &[
+            (KeyChord::key(KeyCode::Home), TextEdit::Home),
+            (KeyChord::ctrl('a'), TextEdit::Home),
+        ],
+        "start of the line",
+    ),
+    (
+        &[
+            (KeyChord::key(KeyCode::End), TextEdit::End),
+            (KeyChord::ctrl('e'), TextEdit::End),
+        ],
+        "end of the line",
+    ),
+    (
+        &[
+            (KeyChord::key(KeyCode::Backspace), TextEdit::Backspace),
+            (KeyChord::key(KeyCode::Delete), TextEdit::Delete),
+        ],
+        "delete a character",
+    ),
+    (
+        &[(KeyChord::ctrl('w'), TextEdit::DeleteWord)],
+        "delete a word",
+    ),
+    (
+        &[(KeyChord::ctrl('u'), TextEdit::DeleteToStart)],
+        "delete to the start",
+    ),
+    (&[(KeyChord::ctrl('z'), TextEdit::Undo)], "undo"),
+    (&[(KeyChord::ctrl('y'), TextEdit::Redo)], "redo"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Insert,
+    Delete,
+    Other,
+}
+
+/// A single line of editable text with a cursor and its own undo history
+#[derive(Debug, Clone, Default)]
+pub struct LineEditor {
+    text: String,
+    /// In characters, from 0 to the length of the text
+    cursor: usize,
+    undo: Vec<(String, usize)>,
+    redo: Vec<(String, usize)>,
+    /// Runs of typing or deleting are undone together
+    last_edit: Option<Edit>,
+}
+
+impl From<&str> for LineEditor {
+    fn from(text: &str) -> LineEditor {
+        LineEditor {
+            text: String::from(text),
+            cursor: text.chars().count(),
+            ..Default::default()
+        }
+    }
+}
+
+impl LineEditor {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replace the text as one undoable step, leaving the cursor at the end
+    pub fn set_text(&mut self, text: &str) {
+        self.record(Edit::Other);
+        self.text = String::from(text);
+        self.cursor = text.chars().count();
+    }
+
+    /// Empty the field and forget its history
+    pub fn reset(&mut self) {
+        *self = LineEditor::default();
+    }
+
+    /// Take the text out, leaving the field reset
+    pub fn take(&mut self) -> String {
+        std::mem::take(self).text
+    }
+
+    pub fn insert_str(&mut self, text: &str) {
+        self.record(Edit::Other);
+        let at = self.byte_idx(self.cursor);
+        self.text.insert_str(at, text);
+        self.cursor += text.chars().count();
+    }
+
+    /// Apply an editing key from `EDIT_KEYS` or type a character,
+    /// returning whether the key was either
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let key = match key.code {
+            // Some terminals send these for backspace
+            KeyCode::Char('\x08' | '\x7f') => KeyEvent::from(KeyCode::Backspace),
+            _ => key,
+        };
+        let edit = EDIT_KEYS
+            .iter()
+            .flat_map(|(keys, _)| keys.iter())
+            .find(|(chord, _)| chord.matches(&key));
+        if let Some(&(_, edit)) = edit {
+            self.apply(edit);
+            return true;
+        }
+        match key.code {
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                self.record(Edit::Insert);
+                let at = self.byte_idx(self.cursor);
+                self.text.insert(at, c);
+                self.cursor += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn apply(&mut self, edit: TextEdit) {
+        match edit {
+            TextEdit::Left => self.move_to(self.cursor.saturating_sub(1)),
+            TextEdit::Right => self.move_to((self.cursor + 1).min(self.len())),
+            TextEdit::WordLeft => self.move_to(self.word_start()),
+            TextEdit::WordRight => self.move_to(self.word_end()),
+            TextEdit::Home => self.move_to(0),
+            TextEdit::End => self.move_to(self.len()),
+            TextEdit::Backspace => {
+                if self.cursor > 0 {
+                    self.record(Edit::Delete);
+                    self.remove(self.cursor - 1, self.cursor);
+                    self.cursor -= 1;
+                }
+            }
+            TextEdit::Delete => {
+                if self.cursor < self.len() {
+                    self.record(Edit::Delete);
+                    self.remove(self.cursor, self.cursor + 1);
+                }
+            }
+            TextEdit::DeleteWord => {
+                let start = self.word_start();
+                self.delete_range(start, self.cursor);
+            }
+            TextEdit::DeleteToStart => self.delete_range(0, self.cursor),
+            TextEdit::Undo => self.undo(),
+            TextEdit::Redo => self.redo(),
+        }
+    }
+
+    pub fn undo(&mut self) {
+        if let Some((text, cursor)) = self.undo.pop() {
+            let current = (std::mem::replace(&mut self.text, text), self.cursor);
+            self.redo.push(current);
+            self.cursor = cursor;
+            self.last_edit = None;
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some((text, cursor)) = self.redo.pop() {
+            let current = (std::mem::replace(&mut self.text, text), self.cursor);
+            self.undo.push(current);
+            self.cursor = cursor;
+            self.last_edit = None;
+        }
+    }
+
+    /// The part of the text that fits in `width` columns, scrolled to keep
+    /// the cursor in view, and the cursor's column within it. `mask`
+    /// replaces every character, for passwords
+    pub fn view(&self, width: usize, mask: Option<char>) -> (String, usize) {
+        if width == 0 {
+            return (String::new(), 0);
+        }
+        // The cursor needs a column of its own at the end of the text
+        let scroll = self.cursor.saturating_sub(width - 1);
+        let visible = self
+            .text
+            .chars()
+            .skip(scroll)
+            .take(width)
+            .map(|c| mask.unwrap_or(c))
+            .collect();
+        (visible, self.cursor - scroll)
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn byte_idx(&self, char_idx: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(char_idx)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+
+    fn move_to(&mut self, cursor: usize) {
+        self.cursor = cursor;
+        self.last_edit = None;
+    }
+
+    fn remove(&mut self, from: usize, to: usize) {
+        let (from, to) = (self.byte_idx(from), self.byte_idx(to));
+        self.text.replace_range(from..to, "");
+    }
+
+    fn delete_range(&mut self, from: usize, to: usize) {
+        if from < to {
+            self.record(Edit::Other);
+            self.remove(from, to);
+            self.cursor = from;
+        }
+    }
+
+    /// Where the word before the cursor starts
+    fn word_start(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut idx = self.cursor;
+        while idx > 0 && chars[idx - 1].is_whitespace() {
+            idx -= 1;
+        }
+        while idx > 0 && !chars[idx - 1].is_whitespace() {
+            idx -= 1;
+        }
+        idx
+    }
+
+    /// Where the word after the cursor ends
+    fn word_end(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut idx = self.cursor;
+        while idx < chars.len() && chars[idx].is_whitespace() {
+            idx += 1;
+        }
+        while idx < chars.len() && !chars[idx].is_whitespace() {
+            idx += 1;
+        }
+        idx
+    }
+
+    /// Remember the text before an edit, unless it continues the last one
+    fn record(&mut self, edit: Edit) {
+        if edit != Edit::Other && self.last_edit == Some(edit) {
+            return;
+        }
+        self.undo.push((self.text.clone(), self.cursor));
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        self.last_edit = Some(edit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_str(editor: &mut LineEditor, text: &str) {
+        for c in text.chars() {
+            editor.handle_key(KeyEvent::from(KeyCode::Char(c)));
+        }
+    }
+
+    fn at(text: &str, cursor: usize) -> LineEditor {
+        LineEditor {
+            cursor,
+            ..LineEditor::from(text)
+        }
+    }
+
+    #[test]
+    fn undoes_a_run_of_typing_at_once() {
+        let mut editor = LineEditor::default();
+        type_str(&mut editor, "hello");
+        editor.apply(TextEdit::Undo);
+        assert_eq!(editor.text(), "");
+        editor.apply(TextEdit::Redo);
+        assert_eq!((editor.text(), editor.cursor), ("hello", 5));
+    }
+
+    #[test]
+    fn moving_the_cursor_starts_a_new_undo_step() {
+        let mut editor = LineEditor::default();
+        type_str(&mut editor, "ab");
+        editor.apply(TextEdit::Left);
+        type_str(&mut editor, "c");
+        assert_eq!(editor.text(), "acb");
+        editor.undo();
+        assert_eq!((editor.text(), editor.cursor), ("ab", 1));
+        editor.undo();
+        assert_eq!(editor.text(), "");
+    }
+
+    #[test]
+    fn undoes_deleting_separately_from_typing() {
+        let mut editor = LineEditor::default();
+        type_str(&mut editor, "abc");
+        editor.handle_key(KeyEvent::from(KeyCode::Backspace));
+        editor.handle_key(KeyEvent::from(KeyCode::Char('\x7f')));
+        assert_eq!(editor.text(), "a");
+        editor.undo();
+        assert_eq!(editor.text(), "abc");
+        editor.undo();
+        assert_eq!(editor.text(), "");
+    }
+
+    #[test]
+    fn forgets_redo_after_a_new_edit() {
+        let mut editor = LineEditor::default();
+        type_str(&mut editor, "a");
+        editor.undo();
+        type_str(&mut editor, "b");
+        editor.redo();
+        assert_eq!(editor.text(), "b");
+    }
+
+    #[test]
+    fn keeps_the_latest_undo_steps() {
+        let mut editor = LineEditor::default();
+        for i in 0..MAX_UNDO + 50 {
+            editor.set_text(&i.to_string());
+        }
+        while !editor.undo.is_empty() {
+            editor.undo();
+        }
+        assert_eq!(editor.text(), "49");
+    }
+
+    #[test]
+    fn jumps_by_word() {
+        let mut editor = LineEditor::from("one two  three");
+        editor.apply(TextEdit::WordLeft);
+        assert_eq!(editor.cursor, 9);
+        editor.apply(TextEdit::WordLeft);
+        assert_eq!(editor.cursor, 4);
+        editor.apply(TextEdit::WordRight);
+        assert_eq!(editor.cursor, 7);
+        editor.apply(TextEdit::WordRight);
+        assert_eq!(editor.cursor, 14);
+        editor.apply(TextEdit::WordRight);
+        assert_eq!(editor.cursor, 14);
+        editor.apply(TextEdit::Home);
+        editor.apply(TextEdit::WordLeft);
+        assert_eq!(editor.cursor, 0);
+    }
+
+    #[test]
+    fn deletes_the_word_before_the_cursor() {
+        let mut editor = LineEditor::from("one two  ");
+        editor.handle_key(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!((editor.text(), editor.cursor), ("one ", 4));
+
+        let mut editor = at("one two three", 7);
+        editor.apply(TextEdit::DeleteWord);
+        assert_eq!((editor.text(), editor.cursor), ("one  three", 4));
+        editor.undo();
+        assert_eq!((editor.text(), editor.cursor), ("one two three", 7));
+    }
+
+    #[test]
+    fn deletes_to_the_start_and_around_multibyte_characters() {
+        let mut editor = at("héllo wörld", 6);
+        editor.apply(TextEdit::DeleteToStart);
+        assert_eq!((editor.text(), editor.cursor), ("wörld", 0));
+
+        let mut editor = at("héllo", 2);
+        editor.apply(TextEdit::Backspace);
+        editor.apply(TextEdit::Delete);
+        assert_eq!((editor.text(), editor.cursor), ("hlo", 1));
+    }
+
+    #[test]
+    fn shows_the_start_until_the_cursor_reaches_the_edge() {
+        let editor = at("abcdefghij", 0);
+        assert_eq!(editor.view(5, None), (String::from("abcde"), 0));
+        let editor = at("abcdefghij", 4);
+        assert_eq!(editor.view(5, None), (String::from("abcde"), 4));
+    }
+
+    #[test]
+    fn scrolls_to_keep_the_cursor_in_view() {
+        let editor = at("abcdefghij", 5);
+        assert_eq!(editor.view(5, None), (String::from("bcdef"), 4));
+        // at the end the cursor takes the last column
+        let editor = LineEditor::from("abcdefghij");
+        assert_eq!(editor.view(5, None), (String::from("ghij"), 4));
+        assert_eq!(editor.view(11, Some('*')), (String::from("**********"), 10));
+        assert_eq!(editor.view(0, None), (String::new(), 0));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::config::{LoginConfig, MaxAttemptsAction};
+use chrono::{DateTime, Local, TimeDelta};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Wrong passwords in a row allowed before each one adds a delay
+const FREE_ATTEMPTS: u32 = 3;
+/// The longest back-off between two attempts, in seconds
+const MAX_BACKOFF_SECS: i64 = 300;
+
+/// Wrong master passwords since the last unlock, kept beside the hash so
+/// restarting grimoire doesn't start the count again
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoginAttempts {
+    pub failures: u32,
+    pub last_failure: Option<DateTime<Local>>,
+}
+
+impl LoginAttempts {
+    /// The saved attempts, or none when the file is missing or unreadable
+    pub fn load(path: &Path) -> LoginAttempts {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the attempts, removing the file once there are none
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if self.failures == 0 {
+            return match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+        fs::write(path, serde_json::to_string(self)?)
+    }
+
+    pub fn record_failure(&mut self) {
+        self.failures += 1;
+        self.last_failure = Some(Local::now());
+    }
+
+    /// Whether `max_attempts` has been reached with lockout chosen
+    pub fn locked_out(&self, config: &LoginConfig) -> bool {
+        config.max_attempts > 0
+            && self.failures >= config.max_attempts
+            && config.on_max_attempts == MaxAttemptsAction::Lockout
+    }
+
+    /// Wrong passwords left before `on_max_attempts`, if there's a limit
+    pub fn attempts_left(&self, config: &LoginConfig) -> Option<u32> {
+        (config.max_attempts > 0).then(|| config.max_attempts.saturating_sub(self.failures))
+    }
+
+    /// How long until the next password will be tried, if it has to wait
+    pub fn wait(&self, config: &LoginConfig) -> Option<TimeDelta> {
+        let delay = if self.locked_out(config) {
+            TimeDelta::minutes(config.lockout_minutes.into())
+        } else {
+            backoff(self.failures)
+        };
+        let remaining = self.last_failure? + delay - Local::now();
+        (remaining > TimeDelta::zero()).then_some(remaining)
+    }
+
+    /// Why no password can be tried yet, if it can't
+    pub fn refusal(&self, config: &LoginConfig) -> Option<String> {
+        let wait = format_wait(self.wait(config)?);
+        Some(if self.locked_out(config) {
+            format!(
+                "Locked out after {} wrong passwords. Try again in {}",
+                self.failures, wait
+            )
+        } else {
+            format!("Too many wrong passwords. Try again in {}", wait)
+        })
+    }
+}
+
+/// Nothing for the first few wrong passwords, then doubling from a second
+fn backoff(failures: u32) -> TimeDelta {
+    if failures < FREE_ATTEMPTS {
+        return TimeDelta::zero();
+    }
+    let doublings = (failures - FREE_ATTEMPTS).min(16);
+    TimeDelta::seconds((1i64 << doublings).min(MAX_BACKOFF_SECS))
+}
+
+fn format_wait(wait: TimeDelta) -> String {
+    // round up, so "0s" is never shown while still waiting
+    let secs = (wait.num_milliseconds() + 999) / 1000;
+    if secs < 60 {
+        format!("{}s", secs)
+    } else {
+        format!("{}m {}s", secs / 60, secs % 60)
+    }
+}
+
+/// Append a timestamped line to the login log
+pub fn log(path: &Path, message: &str) {
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+        let _ = writeln!(file, "{} {}", Local::now().to_rfc3339(), message);
+    }
+}
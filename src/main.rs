@@ -1,8 +1,13 @@
-use cli_clipboard::ClipboardProvider;
-use crossterm::event::{self, Event, KeyCode, KeyModifiers};
-use grimoire::app::{App, CurrentScreen, CurrentlyEditing};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use grimoire::app::{App, ClickTarget, CurrentScreen, CurrentlyEditing, InitStep};
+use grimoire::config::Config;
+use grimoire::generator::GeneratorOverrides;
+use grimoire::health::{self, Severity};
 use grimoire::ipc;
-use grimoire::ipc::{IpcRequest, IpcResponse};
+use grimoire::ipc::{IpcEntry, IpcRequest, IpcResponse};
+use grimoire::keymap::{Action, Context};
 use grimoire::ui::ui;
 use ratatui::backend::Backend;
 use ratatui::crossterm::event::DisableMouseCapture;
@@ -12,10 +17,12 @@ use ratatui::crossterm::terminal::{EnterAlternateScreen, enable_raw_mode};
 use ratatui::crossterm::terminal::{LeaveAlternateScreen, disable_raw_mode};
 use ratatui::{Terminal, backend::CrosstermBackend};
 use std::error::Error;
-use std::io::Read;
-use std::io::{self, Write};
+use std::io::{self, BufReader, IsTerminal, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{RecvTimeoutError, Sender, channel};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 
 #[cfg(unix)]
 fn start_ipc_server(app: Arc<Mutex<App>>) -> thread::JoinHandle<()> {
@@ -93,51 +100,95 @@ fn start_ipc_server(app: Arc<Mutex<App>>) -> thread::JoinHandle<()> {
 }
 
 #[cfg(unix)]
-fn handle_ipc_client(mut stream: std::os::unix::net::UnixStream, app: Arc<Mutex<App>>) {
-    handle_ipc_request(&mut stream, app);
+fn handle_ipc_client(stream: std::os::unix::net::UnixStream, app: Arc<Mutex<App>>) {
+    match stream.try_clone() {
+        Ok(writer) => handle_ipc_connection(stream, writer, app),
+        Err(e) => eprintln!("IPC connection error: {}", e),
+    }
 }
 
 #[cfg(windows)]
-fn handle_ipc_client(mut stream: std::fs::File, app: Arc<Mutex<App>>) {
-    handle_ipc_request(&mut stream, app);
+fn handle_ipc_client(stream: std::fs::File, app: Arc<Mutex<App>>) {
+    match stream.try_clone() {
+        Ok(writer) => handle_ipc_connection(stream, writer, app),
+        Err(e) => eprintln!("IPC connection error: {}", e),
+    }
 }
 
-fn handle_ipc_request<S: Read + Write>(stream: &mut S, app: Arc<Mutex<App>>) {
-    let mut buffer = Vec::new();
-    let mut temp = [0u8; 1024];
+/// Serve requests until the client hangs up. They're answered one at a time
+/// in the order they arrived, so a client can't start unbounded threads and
+/// responses never overtake each other
+fn handle_ipc_connection<R, W>(reader: R, writer: W, app: Arc<Mutex<App>>)
+where
+    R: Read,
+    W: Write + Send + 'static,
+{
+    let writer = Arc::new(Mutex::new(writer));
+    let closed = Arc::new(AtomicBool::new(false));
+    let mut subscribed = false;
+    let mut reader = BufReader::new(reader);
 
-    loop {
-        match stream.read(&mut temp) {
-            Ok(0) => break,
-            Ok(n) => {
-                buffer.extend_from_slice(&temp[..n]);
-                if buffer.ends_with(&[b'\n']) {
-                    break;
-                }
+    while let Ok(Some(line)) = ipc::read_message(&mut reader) {
+        if line.is_empty() {
+            continue;
+        }
+
+        let request = match serde_json::from_slice::<IpcRequest>(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                let response = IpcResponse {
+                    ok: false,
+                    error: Some(format!("Invalid JSON: {}", e)),
+                    ..Default::default()
+                };
+                let _ = ipc::write_message(&mut *writer.lock().unwrap(), &response);
+                continue;
             }
-            Err(_) => return,
+        };
+
+        if request.action == "subscribe" {
+            if !subscribed {
+                subscribed = true;
+                forward_app_events(&app, Arc::clone(&writer), Arc::clone(&closed));
+            }
+            let response = IpcResponse {
+                id: request.id,
+                ok: true,
+                message: Some("Subscribed".to_string()),
+                ..Default::default()
+            };
+            let _ = ipc::write_message(&mut *writer.lock().unwrap(), &response);
+            continue;
         }
-    }
 
-    if buffer.ends_with(&[b'\n']) {
-        buffer.pop();
+        let id = request.id;
+        let mut response = process_request(request, Arc::clone(&app));
+        response.id = id;
+        let _ = ipc::write_message(&mut *writer.lock().unwrap(), &response);
     }
 
-    let response = match serde_json::from_slice::<IpcRequest>(&buffer) {
-        Ok(request) => process_request(request, app),
-        Err(e) => IpcResponse {
-            ok: false,
-            username: None,
-            password: None,
-            message: None,
-            error: Some(format!("Invalid JSON: {}", e)),
-        },
-    };
+    closed.store(true, Ordering::Relaxed);
+}
 
-    let response_json = serde_json::to_string(&response).unwrap();
-    let _ = stream.write_all(response_json.as_bytes());
-    let _ = stream.write_all(b"\n");
-    let _ = stream.flush();
+/// Push every AppEvent to a subscribed connection until it closes
+fn forward_app_events<W>(app: &Arc<Mutex<App>>, writer: Arc<Mutex<W>>, closed: Arc<AtomicBool>)
+where
+    W: Write + Send + 'static,
+{
+    let events = app.lock().unwrap().subscribe();
+    thread::spawn(move || {
+        while !closed.load(Ordering::Relaxed) {
+            match events.recv_timeout(Duration::from_secs(1)) {
+                Ok(event) => {
+                    if ipc::write_message(&mut *writer.lock().unwrap(), &event).is_err() {
+                        break;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    });
 }
 
 fn process_request(request: IpcRequest, app: Arc<Mutex<App>>) -> IpcResponse {
@@ -152,6 +203,7 @@ fn process_request(request: IpcRequest, app: Arc<Mutex<App>>) -> IpcResponse {
                         password: None,
                         message: None,
                         error: Some("App is locked".to_string()),
+                        ..Default::default()
                     }
                 } else {
                     match app.get_credentials_for_domain(&domain) {
@@ -161,6 +213,7 @@ fn process_request(request: IpcRequest, app: Arc<Mutex<App>>) -> IpcResponse {
                             password: Some(password),
                             message: None,
                             error: None,
+                            ..Default::default()
                         },
                         None => IpcResponse {
                             ok: false,
@@ -168,6 +221,7 @@ fn process_request(request: IpcRequest, app: Arc<Mutex<App>>) -> IpcResponse {
                             password: None,
                             message: None,
                             error: Some("No credentials found for domain".to_string()),
+                            ..Default::default()
                         },
                     }
                 }
@@ -178,6 +232,7 @@ fn process_request(request: IpcRequest, app: Arc<Mutex<App>>) -> IpcResponse {
                     password: None,
                     message: None,
                     error: Some("Domain not specified".to_string()),
+                    ..Default::default()
                 }
             }
         }
@@ -193,15 +248,17 @@ fn process_request(request: IpcRequest, app: Arc<Mutex<App>>) -> IpcResponse {
                         password: None,
                         message: None,
                         error: Some("App is locked".to_string()),
+                        ..Default::default()
                     }
                 } else {
-                    app.save_credentials_for_domain(&domain, &username, &password);
+                    app.queue_credentials_for_domain(&domain, Some(&username), &password);
                     IpcResponse {
                         ok: true,
                         username: None,
                         password: None,
-                        message: Some("Credentials saved".to_string()),
+                        message: Some("Credentials queued for review in Grimoire".to_string()),
                         error: None,
+                        ..Default::default()
                     }
                 }
             } else {
@@ -211,6 +268,184 @@ fn process_request(request: IpcRequest, app: Arc<Mutex<App>>) -> IpcResponse {
                     password: None,
                     message: None,
                     error: Some("Domain, username, and password must be specified".to_string()),
+                    ..Default::default()
+                }
+            }
+        }
+        "generate_password" => {
+            let mut app = app.lock().unwrap();
+            let mut profile = match request.profile.as_deref() {
+                Some(name) => app.config.generator_profile(Some(name)).cloned(),
+                None => Some(app.active_generator_profile()),
+            };
+            if let Some(profile) = profile.as_mut() {
+                let overrides = GeneratorOverrides {
+                    length: request.length,
+                    symbols: request.symbols,
+                    passphrase: request.passphrase,
+                    words: request.words,
+                    separator: request.separator.clone(),
+                };
+                overrides.apply(profile);
+            }
+
+            match profile.map(|profile| profile.generate()) {
+                None => IpcResponse {
+                    ok: false,
+                    error: Some("No generator profile with that name".to_string()),
+                    ..Default::default()
+                },
+                Some(Err(e)) => IpcResponse {
+                    ok: false,
+                    error: Some(e),
+                    ..Default::default()
+                },
+                Some(Ok(generated)) if request.save.unwrap_or(false) => match request.domain {
+                    Some(_) if !app.unlocked => IpcResponse {
+                        ok: false,
+                        error: Some("App is locked".to_string()),
+                        ..Default::default()
+                    },
+                    Some(domain) => {
+                        app.queue_credentials_for_domain(
+                            &domain,
+                            request.username.as_deref(),
+                            &generated.password,
+                        );
+                        IpcResponse {
+                            ok: true,
+                            password: Some(generated.password),
+                            entropy: Some(generated.entropy),
+                            message: Some("Password queued for review in Grimoire".to_string()),
+                            ..Default::default()
+                        }
+                    }
+                    None => IpcResponse {
+                        ok: false,
+                        error: Some("Domain must be specified to save a password".to_string()),
+                        ..Default::default()
+                    },
+                },
+                Some(Ok(generated)) => IpcResponse {
+                    ok: true,
+                    password: Some(generated.password),
+                    entropy: Some(generated.entropy),
+                    ..Default::default()
+                },
+            }
+        }
+        "search" => {
+            let app = app.lock().unwrap();
+            if !app.unlocked {
+                IpcResponse {
+                    ok: false,
+                    error: Some("App is locked".to_string()),
+                    ..Default::default()
+                }
+            } else if !app.config.ipc.allow_search {
+                IpcResponse {
+                    ok: false,
+                    error: Some("search is disabled in the ipc config".to_string()),
+                    ..Default::default()
+                }
+            } else {
+                let query = request.query.unwrap_or_default();
+                let entries = app
+                    .find_secrets(&query)
+                    .into_iter()
+                    .map(|idx| {
+                        let secret = &app.secrets[idx];
+                        IpcEntry {
+                            id: secret.get_id().to_string(),
+                            name: secret.get_name().to_string(),
+                            fields: secret
+                                .get_contents()
+                                .into_iter()
+                                .filter(|pair| !pair.is_sensitive())
+                                .collect(),
+                        }
+                    })
+                    .collect();
+                IpcResponse {
+                    ok: true,
+                    entries: Some(entries),
+                    ..Default::default()
+                }
+            }
+        }
+        "get_entry" => {
+            let app = app.lock().unwrap();
+            if !app.unlocked {
+                IpcResponse {
+                    ok: false,
+                    error: Some("App is locked".to_string()),
+                    ..Default::default()
+                }
+            } else if !app.config.ipc.allow_get_entry {
+                IpcResponse {
+                    ok: false,
+                    error: Some("get_entry is disabled in the ipc config".to_string()),
+                    ..Default::default()
+                }
+            } else {
+                match request
+                    .entry_id
+                    .as_deref()
+                    .and_then(|id| app.find_secret_by_id(id))
+                {
+                    Some(secret) => IpcResponse {
+                        ok: true,
+                        entry: Some(IpcEntry {
+                            id: secret.get_id().to_string(),
+                            name: secret.get_name().to_string(),
+                            fields: secret.get_contents(),
+                        }),
+                        ..Default::default()
+                    },
+                    None => IpcResponse {
+                        ok: false,
+                        error: Some("No entry with that ID".to_string()),
+                        ..Default::default()
+                    },
+                }
+            }
+        }
+        "get_field" => {
+            let app = app.lock().unwrap();
+            if !app.unlocked {
+                IpcResponse {
+                    ok: false,
+                    error: Some("App is locked".to_string()),
+                    ..Default::default()
+                }
+            } else if !app.config.ipc.allow_get_field {
+                IpcResponse {
+                    ok: false,
+                    error: Some("get_field is disabled in the ipc config".to_string()),
+                    ..Default::default()
+                }
+            } else {
+                let field = request
+                    .entry_id
+                    .as_deref()
+                    .and_then(|id| app.find_secret_by_id(id))
+                    .and_then(|secret| {
+                        secret
+                            .get_contents()
+                            .into_iter()
+                            .find(|pair| Some(pair.key.as_str()) == request.key.as_deref())
+                    });
+                match field {
+                    Some(pair) => IpcResponse {
+                        ok: true,
+                        value: Some(pair.value),
+                        ..Default::default()
+                    },
+                    None => IpcResponse {
+                        ok: false,
+                        error: Some("No field with that key for that ID".to_string()),
+                        ..Default::default()
+                    },
                 }
             }
         }
@@ -223,6 +458,7 @@ fn process_request(request: IpcRequest, app: Arc<Mutex<App>>) -> IpcResponse {
                     password: None,
                     message: Some("pong".to_string()),
                     error: None,
+                    ..Default::default()
                 }
             } else {
                 IpcResponse {
@@ -231,6 +467,7 @@ fn process_request(request: IpcRequest, app: Arc<Mutex<App>>) -> IpcResponse {
                     password: None,
                     message: None,
                     error: Some("App is locked".to_string()),
+                    ..Default::default()
                 }
             }
         }
@@ -240,427 +477,548 @@ fn process_request(request: IpcRequest, app: Arc<Mutex<App>>) -> IpcResponse {
             password: None,
             message: None,
             error: Some("Unknown action".to_string()),
+            ..Default::default()
         },
     }
 }
 
-#[allow(clippy::single_match)]
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: Arc<Mutex<App>>) -> io::Result<bool> {
-    loop {
-        terminal.draw(|f| {
-            let app = app.lock().unwrap();
-            ui(f, &app);
-        })?;
-        if let Event::Key(key) = event::read()? {
-            if key.kind == event::KeyEventKind::Release {
-                // Skip events that are not KeyEventKind::Press
-                continue;
-            }
-            let mut app = app.lock().unwrap();
-            match app.current_screen {
-                CurrentScreen::Init => match key.code {
-                    KeyCode::Enter => {
-                        app.set_master_password();
-                        app.clear_input_fields();
-                        app.current_screen = CurrentScreen::Main;
-                    }
-                    KeyCode::Esc => return Ok(true),
-                    KeyCode::Backspace | KeyCode::Char('\x08') | KeyCode::Char('\x7f') => {
-                        app.scratch.pop();
-                    }
-                    KeyCode::Char(value) => {
-                        app.scratch.push(value);
-                    }
-                    _ => {}
-                },
-                CurrentScreen::Login => match key.code {
-                    KeyCode::Enter => {
-                        let scratch_clone = app.scratch.clone();
-                        let attempt = app.authenticate(&scratch_clone).unwrap();
-                        if attempt {
-                            app.current_screen = CurrentScreen::Main;
-                        }
-                        app.clear_input_fields();
-                    }
-                    KeyCode::Esc => return Ok(true),
-                    KeyCode::Backspace | KeyCode::Char('\x08') | KeyCode::Char('\x7f') => {
-                        app.scratch.pop();
-                    }
-                    KeyCode::Char(value) => {
-                        app.scratch.push(value);
-                    }
-                    _ => {}
-                },
-                CurrentScreen::Main => match key.code {
-                    KeyCode::Char('q') => return Ok(true),
-                    KeyCode::Esc => {
-                        if let Some(_) = app.currently_selected_secret_idx {
-                            app.currently_selected_secret_idx = None;
-                        } else {
-                            return Ok(true);
-                        }
-                    }
-                    KeyCode::Char('n') => {
-                        app.current_screen = CurrentScreen::New;
-                        app.currently_editing = Some(CurrentlyEditing::Name);
-                    }
-                    KeyCode::Char('x') | KeyCode::Delete => {
-                        app.delete_secret();
-                        app.clear_input_fields();
-                    }
-                    KeyCode::Enter => {
-                        app.load_secret();
-                        app.current_screen = CurrentScreen::Editing;
-                        app.currently_editing = Some(CurrentlyEditing::Name);
-                    }
-                    KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down => {
-                        app.select_new_secret(key.code);
-                    }
-                    KeyCode::Char('/') => {
-                        app.clear_input_fields();
-                        app.current_screen = CurrentScreen::Searching
-                    }
-                    _ => {}
-                },
-                CurrentScreen::Searching => match key.code {
-                    KeyCode::Esc => app.current_screen = CurrentScreen::Main,
-                    KeyCode::Backspace | KeyCode::Char('\x08') | KeyCode::Char('\x7f') => {
-                        if !app.scratch.is_empty() {
-                            app.scratch.pop();
-                        }
-                    }
-                    KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        let text = app.clipboard.get_contents().unwrap();
-                        app.scratch.push_str(&text);
-                    }
-                    KeyCode::Enter => {
+/// How soon a second click on a secret has to follow to open it
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// Clicks select or focus whatever was drawn under them, the wheel scrolls
+fn handle_mouse(app: &mut App, mouse: MouseEvent, last_click: &mut Option<(Instant, usize)>) {
+    if app.show_help {
+        if matches!(mouse.kind, MouseEventKind::Down(_)) {
+            app.show_help = false;
+        }
+        return;
+    }
+    let context = app.key_context();
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            match (context, app.click_target_at(mouse.column, mouse.row)) {
+                (Context::Main | Context::Sidebar, Some(ClickTarget::Secret(idx))) => {
+                    let double = last_click
+                        .is_some_and(|(at, clicked)| clicked == idx && at.elapsed() < DOUBLE_CLICK);
+                    app.sidebar_focused = false;
+                    app.currently_selected_secret_idx = Some(idx);
+                    if double {
+                        *last_click = None;
                         app.load_secret();
                         app.current_screen = CurrentScreen::Editing;
                         app.currently_editing = Some(CurrentlyEditing::Name);
+                    } else {
+                        *last_click = Some((Instant::now(), idx));
                     }
-                    KeyCode::Tab => {
-                        app.increment_search_buffer();
-                    }
-                    KeyCode::Char(value) => {
-                        app.scratch.push(value);
-                        app.search_secrets();
-                    }
-                    _ => {}
-                },
-                CurrentScreen::New => match key.code {
-                    KeyCode::Esc => {
-                        app.current_screen = CurrentScreen::Main;
-                        app.save_secret();
-                        app.clear_input_fields();
-                    }
-                    KeyCode::Tab => {
-                        app.increment_currently_editing();
-                    }
-                    KeyCode::Delete => {
-                        app.delete_pair();
-                    }
-                    KeyCode::Enter => {
-                        app.add_pair();
-                        app.clear_key_value_fields();
-                    }
-                    KeyCode::BackTab => {
-                        app.decrement_currently_editing();
-                    }
-                    KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down => {
-                        app.select_new_pair(key.code);
+                }
+                (Context::Main | Context::Sidebar, Some(ClickTarget::Filter(filter))) => {
+                    app.sidebar_focused = true;
+                    app.set_filter(filter);
+                }
+                (Context::Editor, Some(ClickTarget::Field(field))) => {
+                    app.currently_editing = Some(field);
+                }
+                _ => {}
+            }
+        }
+        MouseEventKind::ScrollDown | MouseEventKind::ScrollUp => {
+            let (step, page) = match mouse.kind {
+                MouseEventKind::ScrollDown => (KeyCode::Down, KeyCode::PageDown),
+                _ => (KeyCode::Up, KeyCode::PageUp),
+            };
+            match context {
+                Context::Main | Context::Sidebar => app.select_new_secret(page),
+                Context::Editor => app.select_new_pair(step),
+                Context::Inbox | Context::Merge => app.select_new_pending(step),
+                Context::Health => app.select_new_issue(step),
+                Context::Trash => app.select_new_trashed(step),
+                Context::InitChoice => app.select_init_option(step),
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Act on a key press, returning true when it's time to quit
+fn handle_key(app: &mut App, key: KeyEvent) -> bool {
+    if key.kind == event::KeyEventKind::Release {
+        // Skip events that are not KeyEventKind::Press
+        return false;
+    }
+    // Feedback lasts until the next key
+    app.status_message = None;
+    if app.show_help {
+        app.show_help = false;
+        return false;
+    }
+    let context = app.key_context();
+    match (context, app.keymap.action(context, &key)) {
+        (_, Some(Action::Help)) => app.show_help = true,
+
+        (Context::Init | Context::InitChoice, Some(Action::Submit)) => app.submit_init_step(),
+        (Context::Init | Context::InitChoice, Some(Action::Back))
+            if app.wizard.step != InitStep::Password =>
+        {
+            app.init_step_back();
+        }
+        (Context::InitChoice, Some(Action::Move)) => app.select_init_option(key.code),
+        (Context::Init | Context::Login, Some(Action::Back)) => return true,
+        (Context::Login, Some(Action::Submit)) => {
+            let typed = app.scratch.text().to_string();
+            let unlocked = if app.recovering {
+                app.unlock_with_recovery_code(&typed)
+            } else {
+                app.unlock(&typed)
+                    .map(|()| app.current_screen = CurrentScreen::Main)
+            };
+            if let Err(message) = unlocked {
+                app.status_message = Some(message);
+            }
+            app.clear_input_fields();
+        }
+        (Context::Login, Some(Action::Recover)) => app.toggle_recovery_code(),
+        (Context::Init | Context::Login, _) => {
+            app.scratch.handle_key(key);
+        }
+
+        (Context::Sidebar, Some(Action::Move)) => app.select_new_filter(key.code),
+        (Context::Sidebar, Some(Action::Back)) => app.sidebar_focused = false,
+        (Context::Main | Context::Sidebar, Some(action)) => match action {
+            Action::Quit => return true,
+            Action::New => {
+                app.current_screen = CurrentScreen::New;
+                app.currently_editing = Some(CurrentlyEditing::Name);
+            }
+            Action::Delete => app.confirm_delete(),
+            Action::Search => {
+                app.clear_input_fields();
+                app.current_screen = CurrentScreen::Searching
+            }
+            Action::Lock => app.lock(),
+            Action::Reveal => app.toggle_reveal(),
+            Action::Sidebar => app.sidebar_focused = true,
+            Action::Back => {
+                if app.currently_selected_secret_idx.is_some() {
+                    app.currently_selected_secret_idx = None;
+                } else {
+                    return true;
+                }
+            }
+            Action::Submit => {
+                app.load_secret();
+                app.current_screen = CurrentScreen::Editing;
+                app.currently_editing = Some(CurrentlyEditing::Name);
+            }
+            Action::Move => app.select_new_secret(key.code),
+            Action::Inbox => {
+                app.clear_input_fields();
+                app.open_inbox();
+            }
+            Action::Health => {
+                app.clear_input_fields();
+                app.open_health_report();
+            }
+            Action::Trash => app.open_trash(),
+            Action::Favorite => app.toggle_favorite(),
+            Action::Sort => app.cycle_sort_mode(),
+            Action::View => app.toggle_view(),
+            Action::Jump => {
+                app.scratch.reset();
+                app.current_screen = CurrentScreen::Jumping;
+            }
+            Action::Tags => app.start_editing_tags(),
+            Action::Folder => app.start_editing_folder(),
+            _ => {}
+        },
+
+        (Context::Prompt, Some(Action::Back)) => {
+            app.scratch.reset();
+            app.current_screen = CurrentScreen::Main;
+        }
+        (Context::Prompt, Some(Action::Submit)) => app.apply_tags_or_folder(),
+        (Context::Prompt, Some(Action::Paste)) => {
+            if let Some(text) = app.clipboard_text() {
+                app.scratch.insert_str(&text);
+            }
+        }
+        (Context::Prompt, _) => {
+            app.scratch.handle_key(key);
+        }
+
+        (Context::Jump, Some(Action::Back)) => {
+            app.scratch.reset();
+            app.current_screen = CurrentScreen::Main;
+        }
+        (Context::Jump, _) => {
+            let typed = app.scratch.handle_key(key);
+            if typed {
+                app.jump_to_typed();
+            }
+        }
+
+        (Context::Health, Some(Action::Back)) => app.current_screen = CurrentScreen::Main,
+        (Context::Health, Some(Action::Move)) => app.select_new_issue(key.code),
+        (Context::Health, Some(Action::Submit)) => app.jump_to_issue(),
+
+        (Context::ConfirmDelete, Some(Action::Submit)) => {
+            app.trash_secret();
+            app.clear_input_fields();
+            app.current_screen = CurrentScreen::Main;
+        }
+        // Anything else keeps the secret
+        (Context::ConfirmDelete, _) => app.current_screen = CurrentScreen::Main,
+
+        (Context::Trash, Some(Action::Back)) => app.current_screen = CurrentScreen::Main,
+        (Context::Trash, Some(Action::Move)) => app.select_new_trashed(key.code),
+        (Context::Trash, Some(Action::Restore)) => app.restore_trashed(),
+        (Context::Trash, Some(Action::Purge)) => app.confirm_purge(),
+        (Context::ConfirmPurge, Some(Action::Submit)) => {
+            app.purge_trashed();
+            app.current_screen = CurrentScreen::Trash;
+        }
+        // Anything else keeps it in the trash
+        (Context::ConfirmPurge, _) => app.current_screen = CurrentScreen::Trash,
+
+        (Context::Inbox, Some(Action::Back)) => app.current_screen = CurrentScreen::Main,
+        (Context::Inbox | Context::Merge, Some(Action::Move)) => {
+            app.select_new_pending(key.code);
+        }
+        (Context::Inbox, Some(Action::Accept)) => app.accept_pending(),
+        (Context::Inbox, Some(Action::Merge)) => app.start_merge_pending(),
+        (Context::Inbox, Some(Action::Discard)) => app.discard_pending(),
+        (Context::Merge, Some(Action::Back)) => app.cancel_merge_pending(),
+        (Context::Merge, Some(Action::Submit)) => app.merge_pending(),
+
+        (Context::Search, Some(Action::Back)) => app.current_screen = CurrentScreen::Main,
+        (Context::Search, Some(Action::Paste)) => {
+            if let Some(text) = app.clipboard_text() {
+                app.scratch.insert_str(&text);
+                app.search_secrets();
+            }
+        }
+        (Context::Search, Some(Action::Submit)) => {
+            app.load_secret();
+            app.current_screen = CurrentScreen::Editing;
+            app.currently_editing = Some(CurrentlyEditing::Name);
+        }
+        (Context::Search, Some(Action::FindNext)) => app.increment_search_buffer(),
+        (Context::Search, _) => {
+            let typed = app.scratch.handle_key(key);
+            if typed {
+                app.search_secrets();
+            }
+        }
+
+        (Context::Discard, Some(Action::Discard)) => app.close_editor(),
+        (Context::Discard, Some(Action::Save)) => {
+            app.confirm_discard = false;
+            app.save_and_close_editor();
+        }
+        (Context::Discard, Some(Action::Back)) => app.confirm_discard = false,
+
+        (Context::Editor, Some(Action::Back)) => {
+            if app.has_unsaved_changes() {
+                app.confirm_discard = true;
+            } else {
+                app.close_editor();
+            }
+        }
+        (Context::Editor, Some(Action::Save)) => app.save_and_close_editor(),
+        (Context::Editor, Some(Action::NextField)) => app.increment_currently_editing(),
+        (Context::Editor, Some(Action::PreviousField)) => {
+            app.decrement_currently_editing();
+        }
+        (Context::Editor, Some(Action::Submit)) => {
+            app.add_pair();
+            app.clear_key_value_fields();
+            if matches!(app.current_screen, CurrentScreen::Editing) {
+                app.increment_currently_editing();
+            }
+        }
+        (Context::Editor, Some(Action::Move)) => app.select_new_pair(key.code),
+        (Context::Editor, Some(Action::DeletePair)) => app.delete_pair(),
+        (Context::Editor, Some(Action::RestorePair)) => app.restore_deleted_pair(),
+        (Context::Editor, Some(Action::Copy)) => {
+            if let Some(text) = app.current_editor().map(|field| field.text().to_string()) {
+                app.copy_to_clipboard(text);
+            }
+        }
+        (Context::Editor, Some(Action::Paste)) => {
+            if let Some(text) = app.clipboard_text()
+                && let Some(field) = app.current_editor()
+            {
+                field.insert_str(&text);
+            }
+        }
+        (Context::Editor, Some(Action::Generate)) => app.generate_into_current_field(),
+        (Context::Editor, Some(Action::CycleProfile)) => app.cycle_generator_profile(),
+        (Context::Editor, _) => {
+            if let Some(field) = app.current_editor() {
+                field.handle_key(key);
+            }
+        }
+
+        _ => {}
+    }
+    false
+}
+
+/// How often the UI wakes up without any input
+const TICK_RATE: Duration = Duration::from_millis(250);
+
+/// What wakes the UI up to redraw
+enum UiEvent {
+    Input(Event),
+    /// Time passed, for anything that changes on its own
+    Tick,
+    /// The app changed, maybe on another thread, e.g. over IPC
+    App,
+    /// The terminal couldn't be read
+    Failed(io::Error),
+}
+
+/// Read the terminal on its own thread, with a tick every `TICK_RATE` in
+/// between. Stops once the UI has gone away.
+fn spawn_input_thread(sender: Sender<UiEvent>) {
+    thread::spawn(move || {
+        let mut last_tick = Instant::now();
+        loop {
+            let timeout = TICK_RATE.saturating_sub(last_tick.elapsed());
+            match event::poll(timeout).and_then(|ready| ready.then(event::read).transpose()) {
+                Ok(Some(event)) => {
+                    if sender.send(UiEvent::Input(event)).is_err() {
+                        return;
                     }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    let _ = sender.send(UiEvent::Failed(e));
+                    return;
+                }
+            }
+            if last_tick.elapsed() >= TICK_RATE {
+                if sender.send(UiEvent::Tick).is_err() {
+                    return;
+                }
+                last_tick = Instant::now();
+            }
+        }
+    });
+}
+
+/// Pass app events on to the UI, so changes made by other threads show up
+/// without waiting for a key
+fn forward_to_ui(app: &Arc<Mutex<App>>, sender: Sender<UiEvent>) {
+    let events = app.lock().unwrap().subscribe();
+    thread::spawn(move || {
+        for _ in events {
+            if sender.send(UiEvent::App).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: Arc<Mutex<App>>) -> io::Result<bool> {
+    let (sender, events) = channel();
+    forward_to_ui(&app, sender.clone());
+    spawn_input_thread(sender);
+
+    let mut last_click = None;
+    loop {
+        terminal.draw(|f| {
+            let mut app = app.lock().unwrap();
+            ui(f, &mut app);
+        })?;
+        // Everything else just needs the redraw above
+        match events.recv() {
+            Ok(UiEvent::Input(Event::Key(key))) => {
+                if handle_key(&mut app.lock().unwrap(), key) {
+                    return Ok(true);
+                }
+            }
+            Ok(UiEvent::Input(Event::Mouse(mouse))) => {
+                handle_mouse(&mut app.lock().unwrap(), mouse, &mut last_click);
+            }
+            Ok(UiEvent::Input(_) | UiEvent::Tick | UiEvent::App) => {}
+            Ok(UiEvent::Failed(e)) => return Err(e),
+            Err(_) => return Ok(true),
+        }
+    }
+}
+
+const USAGE: &str = "\
+usage: grimoire
+       grimoire generate [--profile <name>] [--length <n>] [--no-symbols]
+                         [--passphrase] [--words <n>] [--separator <text>]
+       grimoire health
+
+health exits with 1 when it finds critical issues.";
+
+/// Read the master password without echoing it and unlock the vault
+fn unlock_from_terminal() -> Result<App, String> {
+    let mut app = App::new(Config::load()?);
+    if matches!(app.current_screen, CurrentScreen::Init) {
+        return Err(String::from("No vault yet, run grimoire to create one"));
+    }
+    if let Some(refusal) = app.login_attempts.refusal(&app.config.login) {
+        return Err(refusal);
+    }
+
+    eprint!("Master password: ");
+    io::stderr().flush().map_err(|e| e.to_string())?;
+    let mut password = String::new();
+    if io::stdin().is_terminal() {
+        enable_raw_mode().map_err(|e| e.to_string())?;
+        let read = loop {
+            match event::read() {
+                Ok(Event::Key(key)) if key.kind != event::KeyEventKind::Release => match key.code {
+                    KeyCode::Enter => break Ok(()),
+                    KeyCode::Esc => break Err(String::from("Cancelled")),
                     KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        if let Some(editing) = app.currently_editing.clone() {
-                            let len = app.secret_scratch_content.len();
-                            match editing {
-                                CurrentlyEditing::Name => {
-                                    let text = app.name_input.clone();
-                                    app.clipboard.set_contents(text).unwrap();
-                                }
-                                CurrentlyEditing::Key(idx) => {
-                                    if idx == len {
-                                        let text = app.key_input.clone();
-                                        app.clipboard.set_contents(text).unwrap();
-                                    } else {
-                                        let text = app.secret_scratch_content[idx].key.clone();
-                                        app.clipboard.set_contents(text).unwrap();
-                                    }
-                                }
-                                CurrentlyEditing::Value(idx) => {
-                                    if idx == len {
-                                        let text = app.value_input.clone();
-                                        app.clipboard.set_contents(text).unwrap();
-                                    }
-                                    let text = app.secret_scratch_content[idx].value.clone();
-                                    app.clipboard.set_contents(text).unwrap();
-                                }
-                            }
-                        }
+                        break Err(String::from("Cancelled"));
                     }
-                    KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        if let Some(editing) = app.currently_editing.clone() {
-                            let len = app.secret_scratch_content.len();
-                            match editing {
-                                CurrentlyEditing::Name => {
-                                    let text = app.clipboard.get_contents().unwrap().clone();
-                                    app.name_input.push_str(&text)
-                                }
-                                CurrentlyEditing::Key(idx) => {
-                                    let text = app.clipboard.get_contents().unwrap().clone();
-                                    if idx == len {
-                                        app.key_input.push_str(&text)
-                                    } else {
-                                        app.secret_scratch_content[idx].key.push_str(&text)
-                                    }
-                                }
-                                CurrentlyEditing::Value(idx) => {
-                                    let text = app.clipboard.get_contents().unwrap().clone();
-                                    if idx == len {
-                                        app.value_input.push_str(&text)
-                                    } else {
-                                        app.secret_scratch_content[idx].value.push_str(&text)
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        if let Some(editing) = app.currently_editing.clone() {
-                            let len = app.secret_scratch_content.len();
-                            match editing {
-                                CurrentlyEditing::Name => {
-                                    let generated_name = app.generate_password();
-                                    app.name_input.push_str(&generated_name);
-                                }
-                                CurrentlyEditing::Key(idx) => {
-                                    let generated_key = app.generate_password();
-                                    if idx == len {
-                                        app.key_input.push_str(&generated_key)
-                                    } else {
-                                        app.secret_scratch_content[idx].key.push_str(&generated_key)
-                                    }
-                                }
-                                CurrentlyEditing::Value(idx) => {
-                                    let generated_value = app.generate_password();
-                                    if idx == len {
-                                        app.value_input.push_str(&generated_value)
-                                    } else {
-                                        app.secret_scratch_content[idx]
-                                            .value
-                                            .push_str(&generated_value)
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    KeyCode::Backspace | KeyCode::Char('\x08') | KeyCode::Char('\x7f') => {
-                        if let Some(editing) = &app.currently_editing {
-                            match editing {
-                                CurrentlyEditing::Name => {
-                                    app.name_input.pop();
-                                }
-                                CurrentlyEditing::Key(_) => {
-                                    app.key_input.pop();
-                                }
-                                CurrentlyEditing::Value(_) => {
-                                    app.value_input.pop();
-                                }
-                            }
-                        }
-                    }
-                    KeyCode::Char(value) => {
-                        if let Some(editing) = &app.currently_editing {
-                            match editing {
-                                CurrentlyEditing::Name => app.name_input.push(value),
-                                CurrentlyEditing::Key(_) => app.key_input.push(value),
-                                CurrentlyEditing::Value(_) => app.value_input.push(value),
-                            }
-                        }
+                    KeyCode::Backspace => {
+                        password.pop();
                     }
+                    KeyCode::Char(value) => password.push(value),
                     _ => {}
                 },
-                CurrentScreen::Editing => match key.code {
-                    KeyCode::Esc => {
-                        app.current_screen = CurrentScreen::Main;
-                        app.update_secret();
-                        app.clear_input_fields();
-                    }
-                    KeyCode::Tab => {
-                        app.increment_currently_editing();
-                    }
-                    KeyCode::Delete => {
-                        app.delete_pair();
-                    }
-                    KeyCode::Enter => {
-                        app.add_pair();
-                        app.clear_key_value_fields();
-                        app.increment_currently_editing();
-                    }
-                    KeyCode::BackTab => {
-                        app.decrement_currently_editing();
-                    }
-                    KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down => {
-                        app.select_new_pair(key.code);
-                    }
-                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        if let Some(editing) = app.currently_editing.clone() {
-                            let len = app.secret_scratch_content.len();
-                            match editing {
-                                CurrentlyEditing::Name => {
-                                    let text = app.name_input.clone();
-                                    app.clipboard.set_contents(text).unwrap();
-                                }
-                                CurrentlyEditing::Key(idx) => {
-                                    if idx == len {
-                                        let text = app.key_input.clone();
-                                        app.clipboard.set_contents(text).unwrap();
-                                    } else {
-                                        let text = app.secret_scratch_content[idx].key.clone();
-                                        app.clipboard.set_contents(text).unwrap();
-                                    }
-                                }
-                                CurrentlyEditing::Value(idx) => {
-                                    if idx == len {
-                                        let text = app.value_input.clone();
-                                        app.clipboard.set_contents(text).unwrap();
-                                    }
-                                    let text = app.secret_scratch_content[idx].value.clone();
-                                    app.clipboard.set_contents(text).unwrap();
-                                }
-                            }
-                        }
-                    }
-                    KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        if let Some(editing) = app.currently_editing.clone() {
-                            let len = app.secret_scratch_content.len();
-                            match editing {
-                                CurrentlyEditing::Name => {
-                                    let text = app.clipboard.get_contents().unwrap().clone();
-                                    app.name_input.push_str(&text)
-                                }
-                                CurrentlyEditing::Key(idx) => {
-                                    let text = app.clipboard.get_contents().unwrap().clone();
-                                    if idx == len {
-                                        app.key_input.push_str(&text)
-                                    } else {
-                                        app.secret_scratch_content[idx].key.push_str(&text)
-                                    }
-                                }
-                                CurrentlyEditing::Value(idx) => {
-                                    let text = app.clipboard.get_contents().unwrap().clone();
-                                    if idx == len {
-                                        app.value_input.push_str(&text)
-                                    } else {
-                                        app.secret_scratch_content[idx].value.push_str(&text)
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        if let Some(editing) = app.currently_editing.clone() {
-                            let len = app.secret_scratch_content.len();
-                            match editing {
-                                CurrentlyEditing::Name => {
-                                    let generated_name = app.generate_password();
-                                    app.name_input.push_str(&generated_name);
-                                }
-                                CurrentlyEditing::Key(idx) => {
-                                    let generated_key = app.generate_password();
-                                    if idx == len {
-                                        app.key_input.push_str(&generated_key)
-                                    } else {
-                                        app.secret_scratch_content[idx].key.push_str(&generated_key)
-                                    }
-                                }
-                                CurrentlyEditing::Value(idx) => {
-                                    let generated_value = app.generate_password();
-                                    if idx == len {
-                                        app.value_input.push_str(&generated_value)
-                                    } else {
-                                        app.secret_scratch_content[idx]
-                                            .value
-                                            .push_str(&generated_value)
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    KeyCode::Backspace | KeyCode::Char('\x08') | KeyCode::Char('\x7f') => {
-                        if let Some(editing) = app.currently_editing.clone() {
-                            let len = app.secret_scratch_content.len();
-                            match editing {
-                                CurrentlyEditing::Name => {
-                                    app.name_input.pop();
-                                }
-                                CurrentlyEditing::Key(idx) => {
-                                    if idx == len {
-                                        app.key_input.pop();
-                                    } else {
-                                        app.secret_scratch_content[idx].key.pop();
-                                    }
-                                }
-                                CurrentlyEditing::Value(idx) => {
-                                    if idx == len {
-                                        app.value_input.pop();
-                                    } else {
-                                        app.secret_scratch_content[idx].value.pop();
-                                    }
-                                }
-                            }
-                        }
+                Ok(_) => {}
+                Err(e) => break Err(e.to_string()),
+            }
+        };
+        disable_raw_mode().map_err(|e| e.to_string())?;
+        eprintln!();
+        read?;
+    } else {
+        io::stdin()
+            .read_line(&mut password)
+            .map_err(|e| e.to_string())?;
+        password.truncate(password.trim_end_matches(['\r', '\n']).len());
+    }
+
+    app.unlock(&password)?;
+    Ok(app)
+}
+
+/// Handle a command line subcommand instead of starting the TUI, returning
+/// the exit code
+fn run_cli(args: &[String]) -> Result<i32, String> {
+    match args.first().map(String::as_str) {
+        Some("generate") => {
+            let config = Config::load()?;
+            let mut profile_name = None;
+            let mut overrides = GeneratorOverrides::default();
+            let mut rest = args[1..].iter();
+            while let Some(arg) = rest.next() {
+                let mut value = || rest.next().ok_or(format!("{} needs a value", arg));
+                match arg.as_str() {
+                    "--profile" => profile_name = Some(value()?.clone()),
+                    "--length" => {
+                        overrides.length = Some(value()?.parse().map_err(|_| "Bad --length")?)
                     }
-                    KeyCode::Char(value) => {
-                        if let Some(editing) = app.currently_editing.clone() {
-                            let len = app.secret_scratch_content.len();
-                            match editing {
-                                CurrentlyEditing::Name => app.name_input.push(value),
-                                CurrentlyEditing::Key(idx) => {
-                                    if idx == len {
-                                        app.key_input.push(value)
-                                    } else {
-                                        app.secret_scratch_content[idx].key.push(value)
-                                    }
-                                }
-                                CurrentlyEditing::Value(idx) => {
-                                    if idx == len {
-                                        app.value_input.push(value)
-                                    } else {
-                                        app.secret_scratch_content[idx].value.push(value)
-                                    }
-                                }
-                            }
-                        }
+                    "--no-symbols" => overrides.symbols = Some(false),
+                    "--passphrase" => overrides.passphrase = Some(true),
+                    "--words" => {
+                        overrides.words = Some(value()?.parse().map_err(|_| "Bad --words")?)
                     }
-                    _ => {}
-                },
+                    "--separator" => overrides.separator = Some(value()?.clone()),
+                    _ => return Err(format!("Unknown option {}\n\n{}", arg, USAGE)),
+                }
+            }
+
+            let mut profile = config
+                .generator_profile(profile_name.as_deref())
+                .cloned()
+                .ok_or("No generator profile with that name")?;
+            overrides.apply(&mut profile);
+            let generated = profile.generate()?;
+            println!("{}", generated.password);
+            eprintln!("about {:.0} bits of entropy", generated.entropy);
+            Ok(0)
+        }
+        Some("health") => {
+            if args.len() > 1 {
+                return Err(String::from(USAGE));
+            }
+            let app = unlock_from_terminal()?;
+            let report = health::check(&app.secrets, &app.config.health, &app.breached);
+            if report.is_empty() {
+                println!("No problems found in {} secrets", app.secrets.len());
+            }
+            for severity in Severity::ALL {
+                let issues: Vec<_> = report
+                    .iter()
+                    .filter(|issue| issue.severity == severity)
+                    .collect();
+                if issues.is_empty() {
+                    continue;
+                }
+                println!("{} ({})", severity.name(), issues.len());
+                for issue in issues {
+                    println!("  {}: {}", issue.secret_name, issue.describe());
+                }
             }
+            let critical = report
+                .iter()
+                .any(|issue| issue.severity == Severity::Critical);
+            Ok(if critical { 1 } else { 0 })
         }
+        _ => Err(String::from(USAGE)),
     }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        let code = run_cli(&args).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            2
+        });
+        std::process::exit(code);
+    }
+
+    // Catch config mistakes before the terminal is taken over
+    let config = Config::load().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
+    let problems = [config.keymap.build().err(), config.theme.build().err()];
+    if let Some(e) = problems.into_iter().flatten().next() {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    }
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen)?;
+    // Without capture the terminal keeps the mouse for selecting text
+    let mouse = config.mouse;
+    if mouse {
+        execute!(stdout, EnableMouseCapture)?;
+    }
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let app = Arc::new(Mutex::new(App::new()));
+    let app = Arc::new(Mutex::new(App::new(config)));
 
     // Start IPC server (NEW)
     let _ipc_handle = start_ipc_server(Arc::clone(&app));
 
     let _res = run_app(&mut terminal, Arc::clone(&app));
 
+    // Let subscribed clients know the vault is going away
+    app.lock().unwrap().lock();
+
     // restore terminal
     disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    if mouse {
+        execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    }
     terminal.show_cursor()?;
 
     // Clean up socket file on Unix
@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The name background.js connects to
+pub const HOST_NAME: &str = "com.grimoire.native";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Browser {
+    Firefox,
+    Chrome,
+    Chromium,
+    Brave,
+}
+
+/// The native messaging host manifest browsers use to find grimoire-forwarder
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HostManifest {
+    pub name: String,
+    pub description: String,
+    pub path: PathBuf,
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Firefox lists extension IDs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_extensions: Option<Vec<String>>,
+    /// Chromium-based browsers list extension origins
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_origins: Option<Vec<String>>,
+}
+
+pub enum InstallStatus {
+    NotInstalled,
+    Installed(HostManifest),
+    /// Something is at the manifest path but it can't be read as a manifest
+    Unreadable(io::Error),
+}
+
+impl Browser {
+    pub const ALL: [Browser; 4] = [
+        Browser::Firefox,
+        Browser::Chrome,
+        Browser::Chromium,
+        Browser::Brave,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Browser> {
+        Browser::ALL
+            .into_iter()
+            .find(|browser| browser.name() == name.to_lowercase())
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Browser::Firefox => "firefox",
+            Browser::Chrome => "chrome",
+            Browser::Chromium => "chromium",
+            Browser::Brave => "brave",
+        }
+    }
+
+    /// Where this browser looks for host manifests of the current user
+    #[cfg(target_os = "linux")]
+    pub fn manifest_dir(&self) -> Option<PathBuf> {
+        let home = dirs::home_dir()?;
+        let config = dirs::config_dir()?;
+        Some(match self {
+            Browser::Firefox => home.join(".mozilla/native-messaging-hosts"),
+            Browser::Chrome => config.join("google-chrome/NativeMessagingHosts"),
+            Browser::Chromium => config.join("chromium/NativeMessagingHosts"),
+            Browser::Brave => config.join("BraveSoftware/Brave-Browser/NativeMessagingHosts"),
+        })
+    }
+
+    /// Where this browser looks for host manifests of the current user
+    #[cfg(target_os = "macos")]
+    pub fn manifest_dir(&self) -> Option<PathBuf> {
+        let support = dirs::home_dir()?.join("Library/Application Support");
+        Some(match self {
+            Browser::Firefox => support.join("Mozilla/NativeMessagingHosts"),
+            Browser::Chrome => support.join("Google/Chrome/NativeMessagingHosts"),
+            Browser::Chromium => support.join("Chromium/NativeMessagingHosts"),
+            Browser::Brave => support.join("BraveSoftware/Brave-Browser/NativeMessagingHosts"),
+        })
+    }
+
+    /// Windows finds manifests through the registry, so they're kept with
+    /// grimoire's data and `registry_key` says where to register them
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    pub fn manifest_dir(&self) -> Option<PathBuf> {
+        Some(
+            dirs::data_dir()?
+                .join("grimoire")
+                .join("native-messaging-hosts")
+                .join(self.name()),
+        )
+    }
+
+    pub fn manifest_path(&self) -> Option<PathBuf> {
+        Some(self.manifest_dir()?.join(format!("{}.json", HOST_NAME)))
+    }
+
+    pub fn registry_key(&self) -> String {
+        let vendor = match self {
+            Browser::Firefox => "Mozilla",
+            Browser::Chrome => "Google\\Chrome",
+            Browser::Chromium => "Chromium",
+            Browser::Brave => "BraveSoftware\\Brave-Browser",
+        };
+        format!(
+            "HKCU\\Software\\{}\\NativeMessagingHosts\\{}",
+            vendor, HOST_NAME
+        )
+    }
+
+    fn is_chromium_based(&self) -> bool {
+        !matches!(self, Browser::Firefox)
+    }
+}
+
+/// Chromium extension IDs are 32 letters between a and p
+fn is_chromium_extension_id(id: &str) -> bool {
+    id.len() == 32 && id.chars().all(|c| ('a'..='p').contains(&c))
+}
+
+/// Build the manifest for `browser`, allowing whichever of `extension_ids` it can use
+pub fn build_manifest(
+    browser: Browser,
+    forwarder: &Path,
+    extension_ids: &[String],
+) -> io::Result<HostManifest> {
+    let ids: Vec<&String> = extension_ids
+        .iter()
+        .filter(|id| is_chromium_extension_id(id) == browser.is_chromium_based())
+        .collect();
+    if ids.is_empty() {
+        return Err(io::Error::other(format!(
+            "No {} extension IDs in native_messaging.allowed_extensions",
+            browser.name()
+        )));
+    }
+
+    let (allowed_extensions, allowed_origins) = if browser.is_chromium_based() {
+        let origins = ids
+            .iter()
+            .map(|id| format!("chrome-extension://{}/", id))
+            .collect();
+        (None, Some(origins))
+    } else {
+        (Some(ids.into_iter().cloned().collect()), None)
+    };
+
+    Ok(HostManifest {
+        name: String::from(HOST_NAME),
+        description: String::from("Grimoire password manager"),
+        path: forwarder.to_path_buf(),
+        kind: String::from("stdio"),
+        allowed_extensions,
+        allowed_origins,
+    })
+}
+
+/// Write the manifest for `browser` and return where it went
+pub fn install(
+    browser: Browser,
+    forwarder: &Path,
+    extension_ids: &[String],
+) -> io::Result<PathBuf> {
+    let manifest = build_manifest(browser, forwarder, extension_ids)?;
+    let path = browser
+        .manifest_path()
+        .ok_or_else(|| io::Error::other("Couldn't find the home directory"))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(&manifest)?;
+    fs::write(&path, json)?;
+    Ok(path)
+}
+
+/// Remove the manifest for `browser`, returning its path if there was one
+pub fn uninstall(browser: Browser) -> io::Result<Option<PathBuf>> {
+    let Some(path) = browser.manifest_path() else {
+        return Ok(None);
+    };
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(Some(path)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub fn status(browser: Browser) -> InstallStatus {
+    let Some(path) = browser.manifest_path() else {
+        return InstallStatus::NotInstalled;
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => match serde_json::from_str(&text) {
+            Ok(manifest) => InstallStatus::Installed(manifest),
+            Err(e) => InstallStatus::Unreadable(e.into()),
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound => InstallStatus::NotInstalled,
+        Err(e) => InstallStatus::Unreadable(e),
+    }
+}
@@ -0,0 +1,152 @@
+use crate::kdf::KdfProfile;
+use aes_gcm::{
+    Aes256Gcm, Key, Nonce,
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{Engine as _, engine::general_purpose};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Letters and digits that can't be mistaken for one another on paper
+const ALPHABET: &[u8; 32] = b"ABCDEFGHJKMNPQRSTVWXYZ0123456789";
+const GROUPS: usize = 5;
+const GROUP_LEN: usize = 5;
+
+/// A new recovery code, 125 random bits written as five groups of five
+pub fn new_code() -> String {
+    let mut rng = rand::rng();
+    (0..GROUPS)
+        .map(|_| {
+            (0..GROUP_LEN)
+                .map(|_| ALPHABET[rng.random_range(0..ALPHABET.len())] as char)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// The code as typed, without dashes or spaces, in capitals, and with the
+/// letters left out of the alphabet read as the digits they look like
+fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| match c.to_ascii_uppercase() {
+            'O' => '0',
+            'I' | 'L' => '1',
+            c => c,
+        })
+        .collect()
+}
+
+/// The vault key, encrypted with a key derived from the recovery code and
+/// kept beside the hash. Wrong codes fail to decrypt it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoveryKey {
+    salt: [u8; 16],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    nonce: [u8; 12],
+    wrapped_key: String,
+}
+
+impl RecoveryKey {
+    /// Encrypt the vault key so `code` can open it
+    pub fn seal(vault_key: &[u8; 32], code: &str) -> RecoveryKey {
+        let mut salt = [0u8; 16];
+        rand::rng().fill(&mut salt);
+        let params = KdfProfile::default().params();
+        let mut recovery_key = RecoveryKey {
+            salt,
+            m_cost: params.m_cost(),
+            t_cost: params.t_cost(),
+            p_cost: params.p_cost(),
+            nonce: [0u8; 12],
+            wrapped_key: String::new(),
+        };
+
+        let cipher = Aes256Gcm::new(&recovery_key.wrapping_key(code));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher.encrypt(&nonce, vault_key.as_slice()).unwrap();
+        recovery_key.nonce = nonce.into();
+        recovery_key.wrapped_key = general_purpose::STANDARD.encode(ciphertext);
+        recovery_key
+    }
+
+    /// The vault key, or None if `code` isn't the one it was sealed with
+    pub fn open(&self, code: &str) -> Option<[u8; 32]> {
+        let ciphertext = general_purpose::STANDARD.decode(&self.wrapped_key).ok()?;
+        let cipher = Aes256Gcm::new(&self.wrapping_key(code));
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&self.nonce), ciphertext.as_slice())
+            .ok()?;
+        plaintext.try_into().ok()
+    }
+
+    fn wrapping_key(&self, code: &str) -> Key<Aes256Gcm> {
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, None)
+            .unwrap_or_else(|_| KdfProfile::default().params());
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(normalize(code).as_bytes(), &self.salt, &mut key)
+            .unwrap();
+        key.into()
+    }
+
+    pub fn load(path: &Path) -> io::Result<RecoveryKey> {
+        let text = fs::read_to_string(path)?;
+        serde_json::from_str(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn makes_codes_of_five_groups_from_the_alphabet() {
+        let code = new_code();
+        let groups: Vec<&str> = code.split('-').collect();
+        assert_eq!(groups.len(), GROUPS);
+        for group in groups {
+            assert_eq!(group.len(), GROUP_LEN);
+            assert!(group.bytes().all(|b| ALPHABET.contains(&b)));
+        }
+        assert_ne!(new_code(), code);
+    }
+
+    #[test]
+    fn opens_with_the_code_however_it_is_typed() {
+        let vault_key = [7u8; 32];
+        let recovery_key = RecoveryKey::seal(&vault_key, "AB0C1-DEFGH");
+        assert_eq!(recovery_key.open("AB0C1-DEFGH"), Some(vault_key));
+        assert_eq!(recovery_key.open("ab0c1 defgh"), Some(vault_key));
+        assert_eq!(recovery_key.open("abOcl defgh"), Some(vault_key));
+    }
+
+    #[test]
+    fn refuses_any_other_code() {
+        let recovery_key = RecoveryKey::seal(&[7u8; 32], &new_code());
+        assert_eq!(recovery_key.open(&new_code()), None);
+        assert_eq!(recovery_key.open(""), None);
+    }
+
+    #[test]
+    fn survives_saving_and_loading() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let code = new_code();
+        RecoveryKey::seal(&[9u8; 32], &code)
+            .save(file.path())
+            .unwrap();
+        let loaded = RecoveryKey::load(file.path()).unwrap();
+        assert_eq!(loaded.open(&code), Some([9u8; 32]));
+    }
+}
@@ -4,19 +4,52 @@ use aes_gcm::{
 };
 use base64::{Engine as _, engine::general_purpose};
 use chrono::{DateTime, Local};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Keys whose values are never handed out by search results
+const SENSITIVE_KEYS: [&str; 8] = [
+    "pass", "secret", "token", "pin", "otp", "key", "cvv", "recovery",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Pair {
     pub key: String,
     pub value: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Pair {
+    pub fn is_sensitive(&self) -> bool {
+        let key = self.key.to_lowercase();
+        SENSITIVE_KEYS
+            .iter()
+            .any(|sensitive| key.contains(sensitive))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Secret {
+    /// Empty for secrets saved before IDs existed, until `ensure_id`
+    #[serde(default)]
+    id: String,
     name: String,
     contents: Vec<Pair>,
     last_modified: DateTime<Local>,
+    /// Missing for secrets saved before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_used: Option<DateTime<Local>>,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Slash separated, e.g. "work/servers"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    folder: Option<String>,
+    #[serde(default)]
+    favorite: bool,
+    /// When the secret was moved to the trash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deleted: Option<DateTime<Local>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,20 +58,84 @@ pub struct EncryptedSecret {
     ciphertext: String,
 }
 
+/// Layout of the password store on disk
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EncryptedVault {
+    pub secrets: Vec<EncryptedSecret>,
+    /// Credentials captured by the browser that the user hasn't reviewed yet
+    #[serde(default)]
+    pub pending: Vec<EncryptedSecret>,
+    /// Deleted secrets, kept until they're purged
+    #[serde(default)]
+    pub trash: Vec<EncryptedSecret>,
+}
+
+/// Stores written before the vault had sections are a bare list of secrets
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum StoredVault {
+    Current(EncryptedVault),
+    Legacy(Vec<EncryptedSecret>),
+}
+
+impl From<StoredVault> for EncryptedVault {
+    fn from(stored: StoredVault) -> EncryptedVault {
+        match stored {
+            StoredVault::Current(vault) => vault,
+            StoredVault::Legacy(secrets) => EncryptedVault {
+                secrets,
+                ..Default::default()
+            },
+        }
+    }
+}
+
 impl Secret {
     pub fn new(name: &str, contents: Vec<Pair>) -> Secret {
+        Secret::with_id(&new_id(), name, contents)
+    }
+
+    /// Give a secret saved before IDs existed one, returning whether it
+    /// needed it
+    pub fn ensure_id(&mut self) -> bool {
+        if !self.id.is_empty() {
+            return false;
+        }
+        self.id = new_id();
+        true
+    }
+
+    /// Build a new version of an existing secret, keeping its ID
+    pub fn with_id(id: &str, name: &str, contents: Vec<Pair>) -> Secret {
         Secret {
+            id: String::from(id),
             name: String::from(name),
             contents,
             last_modified: Local::now(),
+            created: Some(Local::now()),
+            last_used: None,
+            tags: Vec::new(),
+            folder: None,
+            favorite: false,
+            deleted: None,
+        }
+    }
+
+    /// A new version of this secret, keeping its ID, tags, folder and favorite
+    pub fn updated(&self, name: &str, contents: Vec<Pair>) -> Secret {
+        Secret {
+            name: String::from(name),
+            contents,
+            last_modified: Local::now(),
+            ..self.clone()
         }
     }
 
     pub fn encrypt(&self, key: [u8; 32]) -> EncryptedSecret {
-        let aes_key = Key::<Aes256Gcm>::from_slice(&key).clone();
+        let aes_key = *Key::<Aes256Gcm>::from_slice(&key);
         let cipher = Aes256Gcm::new(&aes_key);
         let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
-        let ciphertext = cipher.encrypt(&nonce, &*self.to_json().as_ref()).unwrap();
+        let ciphertext = cipher.encrypt(&nonce, self.to_json().as_ref()).unwrap();
         let encoded_ciphertext = general_purpose::STANDARD.encode(&ciphertext);
         EncryptedSecret {
             nonce: nonce.into(),
@@ -50,8 +147,12 @@ impl Secret {
         serde_json::to_string(self).unwrap()
     }
 
-    pub fn from_json(json: String) -> Secret {
-        serde_json::from_str(json.as_str()).unwrap()
+    pub fn from_json(json: String) -> serde_json::Result<Secret> {
+        serde_json::from_str(json.as_str())
+    }
+
+    pub fn get_id(&self) -> &str {
+        self.id.as_str()
     }
 
     pub fn get_name(&self) -> &str {
@@ -61,17 +162,99 @@ impl Secret {
     pub fn get_contents(&self) -> Vec<Pair> {
         self.contents.clone()
     }
+
+    pub fn get_last_modified(&self) -> DateTime<Local> {
+        self.last_modified
+    }
+
+    /// When the secret was made, or last changed if that wasn't recorded
+    pub fn get_created(&self) -> DateTime<Local> {
+        self.created.unwrap_or(self.last_modified)
+    }
+
+    pub fn get_last_used(&self) -> Option<DateTime<Local>> {
+        self.last_used
+    }
+
+    pub fn mark_used(&mut self) {
+        self.last_used = Some(Local::now());
+    }
+
+    pub fn get_tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Tags are kept trimmed, without duplicates
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags.clear();
+        for tag in tags {
+            let tag = tag.trim();
+            if !tag.is_empty() && !self.has_tag(tag) {
+                self.tags.push(String::from(tag));
+            }
+        }
+    }
+
+    pub fn get_folder(&self) -> Option<&str> {
+        self.folder.as_deref()
+    }
+
+    /// Whether the secret is in `folder` or one of its subfolders
+    pub fn in_folder(&self, folder: &str) -> bool {
+        self.folder.as_deref().is_some_and(|own| {
+            own == folder
+                || own
+                    .strip_prefix(folder)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+    }
+
+    /// An empty path takes the secret out of any folder
+    pub fn set_folder(&mut self, folder: &str) {
+        let folder = folder.trim().trim_matches('/');
+        self.folder = (!folder.is_empty()).then(|| String::from(folder));
+    }
+
+    pub fn is_favorite(&self) -> bool {
+        self.favorite
+    }
+
+    pub fn set_favorite(&mut self, favorite: bool) {
+        self.favorite = favorite;
+    }
+
+    pub fn get_deleted(&self) -> Option<DateTime<Local>> {
+        self.deleted
+    }
+
+    /// Record that the secret is in the trash, or take it out with `false`
+    pub fn mark_deleted(&mut self, deleted: bool) {
+        self.deleted = deleted.then(Local::now);
+    }
+}
+
+/// A random ID, unique for all practical purposes
+fn new_id() -> String {
+    format!("{:016x}", rand::rng().random::<u64>())
 }
 
 impl EncryptedSecret {
-    pub fn decrypt(&self, key: [u8; 32]) -> Secret {
-        let ciphertext = general_purpose::STANDARD.decode(&self.ciphertext).unwrap();
+    pub fn decrypt(&self, key: [u8; 32]) -> Result<Secret, String> {
+        let ciphertext = general_purpose::STANDARD
+            .decode(&self.ciphertext)
+            .map_err(|e| format!("a secret's ciphertext isn't valid base64: {}", e))?;
         let aes_key = Key::<Aes256Gcm>::from_slice(&key);
         let cipher = Aes256Gcm::new(aes_key);
         let nonce = Nonce::from_slice(&self.nonce);
-        let plaintext = cipher
-            .decrypt(nonce, ciphertext.as_slice())
-            .expect("Couldn't decrypt, secret was malformed - potentially tampered with");
-        Secret::from_json(String::from_utf8(plaintext).unwrap())
+        let plaintext = cipher.decrypt(nonce, ciphertext.as_slice()).map_err(|_| {
+            String::from("couldn't decrypt a secret, it was malformed - potentially tampered with")
+        })?;
+        let json = String::from_utf8(plaintext)
+            .map_err(|e| format!("a decrypted secret isn't UTF-8: {}", e))?;
+        Secret::from_json(json).map_err(|e| format!("a decrypted secret is malformed: {}", e))
     }
 }
@@ -0,0 +1,325 @@
+use crate::wordlist;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{BuildHasher, RandomState};
+use std::sync::OnceLock;
+
+/// Passwords and words guessers try first, most common first
+const COMMON: &[&str] = &[
+    "password", "123456", "qwerty", "letmein", "welcome", "monkey", "dragon", "master", "football",
+    "baseball", "iloveyou", "admin", "login", "princess", "sunshine", "shadow", "superman",
+    "batman", "trustno1", "hello", "freedom", "whatever", "michael", "jennifer", "hunter",
+    "ranger", "secret", "summer", "winter", "spring", "autumn", "flower", "cheese", "computer",
+    "internet", "soccer", "hockey", "killer", "charlie", "thomas", "jordan", "starwars", "pokemon",
+    "matrix", "access", "mustang", "pepper", "ginger", "cookie", "banana", "orange", "purple",
+    "silver", "golden", "diamond", "tigger", "maggie", "buster", "daniel", "andrew", "joshua",
+    "george", "robert", "jessica", "ashley", "amanda", "nicole", "love", "god", "sex", "money",
+    "magic", "angel", "lucky", "happy", "pass", "test", "user", "guest", "root", "default",
+    "changeme", "grimoire", "vault", "wizard", "spell", "book", "apple", "google", "facebook",
+    "linux", "windows", "ubuntu", "server", "office", "home", "family", "friend", "london",
+    "paris", "berlin", "dallas", "chicago", "boston", "china",
+];
+
+/// Keyboard rows walked for patterns like "qwerty" or "asdf"
+const KEYBOARD_ROWS: &[&str] = &["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+/// Digits and symbols commonly standing in for letters
+const LEET: &[(char, char)] = &[
+    ('4', 'a'),
+    ('@', 'a'),
+    ('8', 'b'),
+    ('3', 'e'),
+    ('6', 'g'),
+    ('1', 'i'),
+    ('!', 'i'),
+    ('0', 'o'),
+    ('5', 's'),
+    ('$', 's'),
+    ('7', 't'),
+];
+
+/// Longest dictionary word looked for
+const MAX_WORD: usize = 16;
+
+/// Longest stretch matched as one pattern, so long values stay quick to
+/// estimate. Longer repeats and sequences are matched in pieces
+const MAX_MATCH: usize = 32;
+
+/// Passwords whose estimate is remembered, about one per field on screen
+const CACHED: usize = 8;
+
+/// How hard a password would be to guess
+#[derive(Debug, Clone)]
+pub struct Strength {
+    /// Bits of entropy under the cheapest way found to guess it
+    pub entropy: f64,
+    /// 0 (very weak) to 4 (very strong)
+    pub score: u8,
+    /// The biggest weakness found, if any
+    pub warning: Option<&'static str>,
+}
+
+impl Strength {
+    pub fn label(&self) -> &'static str {
+        match self.score {
+            0 => "very weak",
+            1 => "weak",
+            2 => "fair",
+            3 => "strong",
+            _ => "very strong",
+        }
+    }
+}
+
+/// A stretch of the password and what guessing it costs
+#[derive(Clone, Copy)]
+struct Segment {
+    bits: f64,
+    warning: Option<&'static str>,
+}
+
+/// Estimate guessing entropy the way zxcvbn does: find the cheapest split of
+/// the password into dictionary words, repeats, sequences, keyboard walks,
+/// years and brute-forced characters
+pub fn estimate(password: &str) -> Strength {
+    let chars: Vec<char> = password.chars().collect();
+    let lower: Vec<char> = chars.iter().flat_map(|c| c.to_lowercase()).collect();
+    // Lowercasing can change the length of some scripts, give up on patterns then
+    let lower = if lower.len() == chars.len() {
+        lower
+    } else {
+        chars.clone()
+    };
+    let n = chars.len();
+    let brute_bits = cardinality(&chars).log2();
+
+    // best[i] is the cheapest way to guess the first i characters
+    let mut best: Vec<Option<(f64, usize, Option<&'static str>)>> = vec![None; n + 1];
+    best[0] = Some((0.0, 0, None));
+    for end in 1..=n {
+        let mut candidates = vec![(
+            end - 1,
+            Segment {
+                bits: brute_bits,
+                warning: None,
+            },
+        )];
+        for start in end.saturating_sub(MAX_MATCH)..end {
+            if let Some(segment) = match_segment(&chars[start..end], &lower[start..end]) {
+                candidates.push((start, segment));
+            }
+        }
+        for (start, segment) in candidates {
+            let Some((bits, _, _)) = best[start] else {
+                continue;
+            };
+            // Every extra pattern is one more thing the guesser has to get right
+            let total = bits + segment.bits + if start > 0 { 1.0 } else { 0.0 };
+            if best[end].is_none_or(|(current, _, _)| total < current) {
+                best[end] = Some((total, start, segment.warning));
+            }
+        }
+    }
+
+    let (entropy, warning) = match best[n] {
+        Some((entropy, _, _)) if n > 0 => {
+            // Report a weakness from the patterns the cheapest guess used
+            let mut warning = None;
+            let mut end = n;
+            while end > 0 {
+                let (_, start, segment_warning) = best[end].expect("Filled above");
+                warning = warning.or(segment_warning);
+                end = start;
+            }
+            (entropy, warning)
+        }
+        _ => (0.0, None),
+    };
+    let warning = warning.or(if n < 8 { Some("Too short") } else { None });
+
+    Strength {
+        entropy,
+        score: score(entropy),
+        warning,
+    }
+}
+
+/// `estimate`, remembered for the last few passwords so fields redrawn on
+/// every tick aren't estimated again. Keyed on a hash, so no password is kept
+pub fn estimate_cached(password: &str) -> Strength {
+    thread_local! {
+        static HASHER: RandomState = RandomState::new();
+        static CACHE: RefCell<VecDeque<(u64, Strength)>> = const { RefCell::new(VecDeque::new()) };
+    }
+    let key = HASHER.with(|hasher| hasher.hash_one(password));
+    CACHE.with_borrow_mut(|cache| {
+        let strength = match cache.iter().position(|(cached, _)| *cached == key) {
+            Some(idx) => cache.remove(idx).expect("Found above").1,
+            None => estimate(password),
+        };
+        cache.push_front((key, strength.clone()));
+        cache.truncate(CACHED);
+        strength
+    })
+}
+
+fn score(entropy: f64) -> u8 {
+    match entropy {
+        e if e < 28.0 => 0,
+        e if e < 40.0 => 1,
+        e if e < 60.0 => 2,
+        e if e < 80.0 => 3,
+        _ => 4,
+    }
+}
+
+/// The size of the character pool a brute-force guesser would need
+fn cardinality(chars: &[char]) -> f64 {
+    let mut size = 0.0;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        size += 26.0;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        size += 26.0;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        size += 10.0;
+    }
+    if chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' ') {
+        size += 33.0;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        size += 100.0;
+    }
+    f64::max(size, 1.0)
+}
+
+/// The cheapest pattern covering all of `chars`, if it is one
+fn match_segment(chars: &[char], lower: &[char]) -> Option<Segment> {
+    let len = chars.len();
+    if len < 3 {
+        return None;
+    }
+    let mut matches = Vec::new();
+
+    // aaaa
+    if chars.iter().all(|c| *c == chars[0]) {
+        matches.push(Segment {
+            bits: cardinality(&chars[..1]).log2() + (len as f64).log2(),
+            warning: Some("Repeated characters are easy to guess"),
+        });
+    }
+
+    // abcd, 9876
+    if let Some(delta) = sequence_delta(lower) {
+        let base = if lower[0].is_ascii_digit() {
+            10.0
+        } else {
+            26.0
+        };
+        matches.push(Segment {
+            bits: f64::log2(base) + (len as f64).log2() + if delta < 0 { 1.0 } else { 0.0 },
+            warning: Some("Sequences like abc or 654 are easy to guess"),
+        });
+    }
+
+    // qwerty, asdf
+    if (4..=KEYBOARD_ROWS[0].len()).contains(&len) && is_keyboard_walk(lower) {
+        matches.push(Segment {
+            bits: f64::log2(47.0) + (len as f64).log2() + 1.0,
+            warning: Some("Keyboard patterns are easy to guess"),
+        });
+    }
+
+    // 1987, 2024
+    if len == 4
+        && (lower.starts_with(&['1', '9']) || lower.starts_with(&['2', '0']))
+        && lower.iter().all(|c| c.is_ascii_digit())
+    {
+        matches.push(Segment {
+            bits: f64::log2(200.0),
+            warning: Some("Years are easy to guess"),
+        });
+    }
+
+    // Dictionary words, plain or with l33t substitutions
+    if len <= MAX_WORD {
+        let word: String = lower.iter().collect();
+        let plain: String = lower.iter().map(|c| unleet(*c)).collect();
+        let substitutions = lower.iter().filter(|c| unleet(**c) != **c).count();
+        let found = dictionary()
+            .get(&word)
+            .map(|rank| (*rank, 0))
+            .or_else(|| dictionary().get(&plain).map(|rank| (*rank, substitutions)));
+        if let Some((rank, substitutions)) = found {
+            matches.push(Segment {
+                bits: ((rank + 1) as f64).log2().max(1.0)
+                    + uppercase_bits(chars)
+                    + substitutions as f64,
+                warning: Some(if rank < COMMON.len() {
+                    "Common passwords and words are easy to guess"
+                } else {
+                    "Single words are easy to guess"
+                }),
+            });
+        }
+    }
+
+    matches.into_iter().min_by(|a, b| a.bits.total_cmp(&b.bits))
+}
+
+/// The step between characters if they all follow on from each other
+fn sequence_delta(chars: &[char]) -> Option<i32> {
+    let delta = chars[1] as i32 - chars[0] as i32;
+    if delta.abs() != 1 || !chars[0].is_ascii_alphanumeric() {
+        return None;
+    }
+    chars
+        .windows(2)
+        .all(|pair| pair[1] as i32 - pair[0] as i32 == delta && pair[1].is_ascii_alphanumeric())
+        .then_some(delta)
+}
+
+fn is_keyboard_walk(chars: &[char]) -> bool {
+    let word: String = chars.iter().collect();
+    let reversed: String = chars.iter().rev().collect();
+    KEYBOARD_ROWS
+        .iter()
+        .any(|row| row.contains(&word) || row.contains(&reversed))
+}
+
+fn unleet(c: char) -> char {
+    LEET.iter()
+        .find(|(from, _)| *from == c)
+        .map_or(c, |(_, to)| *to)
+}
+
+/// "Password" and "PASSWORD" cost a bit more than "password", mixed case more
+fn uppercase_bits(chars: &[char]) -> f64 {
+    let upper = chars.iter().filter(|c| c.is_uppercase()).count();
+    let letters = chars.iter().filter(|c| c.is_alphabetic()).count();
+    if upper == 0 {
+        0.0
+    } else if upper == letters || (upper == 1 && chars[0].is_uppercase()) {
+        1.0
+    } else {
+        upper.min(letters - upper) as f64 + 1.0
+    }
+}
+
+/// Word to rank, common passwords first then the EFF list when it's bundled
+fn dictionary() -> &'static HashMap<String, usize> {
+    static DICTIONARY: OnceLock<HashMap<String, usize>> = OnceLock::new();
+    DICTIONARY.get_or_init(|| {
+        let mut dictionary = HashMap::new();
+        let words = COMMON
+            .iter()
+            .map(|word| word.to_string())
+            .chain(wordlist::load("eff_large").unwrap_or_default());
+        for word in words {
+            let rank = dictionary.len();
+            dictionary.entry(word).or_insert(rank);
+        }
+        dictionary
+    })
+}
@@ -0,0 +1,232 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// How each part of the TUI is drawn
+#[derive(Debug, Clone)]
+pub struct Theme {
+    /// Plain text and unselected cards
+    pub text: Style,
+    /// Hints, placeholders and metadata
+    pub dim: Style,
+    pub border: Style,
+    /// Borders of whatever has focus
+    pub focus: Style,
+    /// The highlighted card, row or item
+    pub selected: Style,
+    /// Added to the field the cursor is in
+    pub editing: Style,
+    /// Secret names and headings
+    pub title: Style,
+    /// Art and other decoration
+    pub accent: Style,
+    /// Field names
+    pub key: Style,
+    /// Field values
+    pub value: Style,
+    /// Text being typed into a single-line input
+    pub input: Style,
+    pub footer: Style,
+    pub status: Style,
+    /// Breaches, critical issues and deleting
+    pub danger: Style,
+    pub warning: Style,
+    pub success: Style,
+    pub info: Style,
+}
+
+/// Ready-made themes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemePreset {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+    Monochrome,
+}
+
+/// A style given in the config file, e.g. `{ fg = "black", bg = "cyan", bold = true }`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoleStyle {
+    /// A color name like "cyan" or "light_red", an index or "#rrggbb"
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underlined: bool,
+    pub reversed: bool,
+}
+
+impl RoleStyle {
+    fn to_style(&self) -> Result<Style, String> {
+        let color =
+            |name: &str| Color::from_str(name).map_err(|_| format!("Unknown color {}", name));
+        let mut style = Style::default();
+        if let Some(fg) = &self.fg {
+            style = style.fg(color(fg)?);
+        }
+        if let Some(bg) = &self.bg {
+            style = style.bg(color(bg)?);
+        }
+        for (set, modifier) in [
+            (self.bold, Modifier::BOLD),
+            (self.dim, Modifier::DIM),
+            (self.italic, Modifier::ITALIC),
+            (self.underlined, Modifier::UNDERLINED),
+            (self.reversed, Modifier::REVERSED),
+        ] {
+            if set {
+                style = style.add_modifier(modifier);
+            }
+        }
+        Ok(style)
+    }
+}
+
+/// The `[theme]` config section: a preset, and styles for any roles that
+/// should differ from it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub preset: ThemePreset,
+    #[serde(flatten)]
+    pub roles: BTreeMap<String, RoleStyle>,
+}
+
+impl ThemeConfig {
+    /// Parse the styles, so mistakes are found at startup. Colors are left
+    /// out when NO_COLOR is set
+    pub fn build(&self) -> Result<Theme, String> {
+        let mut theme = Theme::preset(self.preset);
+        for (name, role) in &self.roles {
+            let style = role.to_style()?;
+            *theme
+                .role_mut(name)
+                .ok_or(format!("Unknown style {} in [theme]", name))? = style;
+        }
+        if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return Ok(Theme::preset(ThemePreset::Monochrome));
+        }
+        Ok(theme)
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::preset(ThemePreset::Dark)
+    }
+}
+
+impl Theme {
+    pub fn preset(preset: ThemePreset) -> Theme {
+        let fg = |color| Style::default().fg(color);
+        let plain = Style::default();
+        match preset {
+            ThemePreset::Dark => Theme {
+                text: fg(Color::White),
+                dim: fg(Color::DarkGray),
+                border: fg(Color::White),
+                focus: fg(Color::Cyan),
+                selected: fg(Color::Black).bg(Color::Cyan),
+                editing: plain.bg(Color::DarkGray),
+                title: fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                accent: fg(Color::Cyan),
+                key: fg(Color::Yellow),
+                value: plain,
+                input: fg(Color::Yellow),
+                footer: fg(Color::Red),
+                status: fg(Color::Yellow),
+                danger: fg(Color::Red),
+                warning: fg(Color::Yellow),
+                success: fg(Color::Green),
+                info: fg(Color::Blue),
+            },
+            ThemePreset::Light => Theme {
+                text: fg(Color::Black),
+                dim: fg(Color::DarkGray),
+                border: fg(Color::DarkGray),
+                focus: fg(Color::Blue),
+                selected: fg(Color::White).bg(Color::Blue),
+                editing: fg(Color::Black).bg(Color::Gray),
+                title: fg(Color::Blue).add_modifier(Modifier::BOLD),
+                accent: fg(Color::Blue),
+                key: fg(Color::Magenta),
+                value: plain,
+                input: fg(Color::Blue),
+                footer: fg(Color::Red),
+                status: fg(Color::Magenta),
+                danger: fg(Color::Red),
+                warning: fg(Color::Magenta),
+                success: fg(Color::Green),
+                info: fg(Color::Blue),
+            },
+            ThemePreset::HighContrast => Theme {
+                text: fg(Color::White),
+                dim: fg(Color::White),
+                border: fg(Color::White),
+                focus: fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+                selected: fg(Color::Black)
+                    .bg(Color::LightYellow)
+                    .add_modifier(Modifier::BOLD),
+                editing: plain.add_modifier(Modifier::REVERSED),
+                title: fg(Color::LightCyan).add_modifier(Modifier::BOLD),
+                accent: fg(Color::LightCyan),
+                key: fg(Color::LightYellow),
+                value: fg(Color::White),
+                input: fg(Color::LightYellow),
+                footer: fg(Color::White).add_modifier(Modifier::BOLD),
+                status: fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+                danger: fg(Color::LightRed).add_modifier(Modifier::BOLD),
+                warning: fg(Color::LightYellow),
+                success: fg(Color::LightGreen),
+                info: fg(Color::LightCyan),
+            },
+            ThemePreset::Monochrome => Theme {
+                text: plain,
+                dim: plain.add_modifier(Modifier::DIM),
+                border: plain,
+                focus: plain.add_modifier(Modifier::BOLD),
+                selected: plain.add_modifier(Modifier::REVERSED),
+                editing: plain.add_modifier(Modifier::UNDERLINED),
+                title: plain.add_modifier(Modifier::BOLD),
+                accent: plain,
+                key: plain.add_modifier(Modifier::BOLD),
+                value: plain,
+                input: plain,
+                footer: plain,
+                status: plain.add_modifier(Modifier::BOLD),
+                danger: plain.add_modifier(Modifier::BOLD),
+                warning: plain.add_modifier(Modifier::BOLD),
+                success: plain,
+                info: plain,
+            },
+        }
+    }
+
+    fn role_mut(&mut self, name: &str) -> Option<&mut Style> {
+        Some(match name {
+            "text" => &mut self.text,
+            "dim" => &mut self.dim,
+            "border" => &mut self.border,
+            "focus" => &mut self.focus,
+            "selected" => &mut self.selected,
+            "editing" => &mut self.editing,
+            "title" => &mut self.title,
+            "accent" => &mut self.accent,
+            "key" => &mut self.key,
+            "value" => &mut self.value,
+            "input" => &mut self.input,
+            "footer" => &mut self.footer,
+            "status" => &mut self.status,
+            "danger" => &mut self.danger,
+            "warning" => &mut self.warning,
+            "success" => &mut self.success,
+            "info" => &mut self.info,
+            _ => return None,
+        })
+    }
+}
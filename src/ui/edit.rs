@@ -1,28 +1,38 @@
-use super::utils::centered_rect;
-use crate::app::{App, CurrentlyEditing};
+use super::LineEditor;
+use super::utils::{centered_rect, render_line_input, strength_bar};
+use crate::app::{App, ClickTarget, CurrentlyEditing};
+use crate::keymap::Action;
+use crate::secret::Pair;
+use crate::strength;
+use crate::theme::Theme;
 use ratatui::{
     Frame,
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Style},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
 };
 
-pub fn render_edit_popup(frame: &mut Frame, app: &App) {
+/// Columns a pair row spends on the indent and the " : " between key and value
+const PAIR_PADDING: usize = 5;
+
+pub fn render_edit_popup(frame: &mut Frame, app: &mut App) {
     frame.render_widget(Clear, frame.area());
     let full_area = centered_rect(70, 80, frame.area());
 
-    let pairs_to_render = app.secret_scratch_content.clone();
-    let name_to_render = app.name_input.clone();
+    let pairs_to_render = &app.secret_scratch_content;
 
     // Layout: name, spacer, each pair, new entry, hint
     let mut constraints = vec![
         Constraint::Length(3), // name field
         Constraint::Length(1), // spacer
     ];
-    constraints.extend(std::iter::repeat(Constraint::Length(3)).take(pairs_to_render.len()));
+    constraints.extend(std::iter::repeat_n(
+        Constraint::Length(3),
+        pairs_to_render.len(),
+    ));
     constraints.push(Constraint::Length(3)); // new entry
     constraints.push(Constraint::Length(1)); // hint
+    constraints.push(Constraint::Length(1)); // status
 
     let layout_chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -30,38 +40,48 @@ pub fn render_edit_popup(frame: &mut Frame, app: &App) {
         .constraints(constraints)
         .split(full_area);
 
+    let theme = &app.theme;
+    let mut targets = vec![(layout_chunks[0], ClickTarget::Field(CurrentlyEditing::Name))];
+
+    // The confirmation covers the fields, so the cursor goes away with it
+    let show_cursor = !app.confirm_discard;
+
     // --- Name field ---
-    let name_border_style = if matches!(app.currently_editing, Some(CurrentlyEditing::Name)) {
-        Style::default().fg(Color::Cyan)
+    let editing_name = matches!(app.currently_editing, Some(CurrentlyEditing::Name));
+    let name_border_style = if editing_name {
+        theme.focus
     } else {
-        Style::default().fg(Color::White)
+        theme.border
     };
 
-    let name_field = Paragraph::new(name_to_render.clone())
-        .alignment(Alignment::Center)
-        .style(if name_to_render.is_empty() {
-            Style::default().fg(Color::DarkGray)
-        } else {
-            Style::default().fg(Color::Green)
-        })
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_type(BorderType::Rounded)
-                .border_style(name_border_style)
-                .title("Name"),
-        );
-
-    frame.render_widget(name_field, layout_chunks[0]);
+    let name_block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(name_border_style)
+        .title("Name");
+    render_line_input(
+        frame,
+        &app.name_input,
+        name_block,
+        layout_chunks[0],
+        theme.input,
+        None,
+        editing_name && show_cursor,
+    );
 
     // Calculate the longest key length (including the new entry inputs)
     let longest_key = pairs_to_render
         .iter()
-        .map(|p| p.key.len())
-        .chain(std::iter::once(app.key_input.len()))
+        .map(|p| p.key.text().chars().count())
+        .chain(std::iter::once(app.key_input.text().chars().count()))
         .max()
         .unwrap_or(0);
 
+    // Keys get at most half of the row, values the rest
+    let row_width = (full_area.width as usize).saturating_sub(4 + 2 + PAIR_PADDING);
+    let key_width = longest_key.min(row_width / 2);
+    let value_width = row_width - key_width;
+
     // --- Pairs ---
     let offset = 2;
     for (i, pair) in pairs_to_render.iter().enumerate() {
@@ -71,92 +91,200 @@ pub fn render_edit_popup(frame: &mut Frame, app: &App) {
             matches!(app.currently_editing, Some(CurrentlyEditing::Value(idx)) if i == idx);
         let selected = editing_key || editing_value;
 
-        let border_style = if selected {
-            Style::default().fg(Color::Cyan)
-        } else {
-            Style::default().fg(Color::White)
-        };
+        let border_style = if selected { theme.focus } else { theme.border };
 
         let key_style = if editing_key {
-            Style::default().fg(Color::Cyan).bg(Color::DarkGray)
+            theme.key.patch(theme.editing)
         } else {
-            Style::default().fg(Color::Cyan)
+            theme.key
         };
 
         let value_style = if editing_value {
-            Style::default().fg(Color::Yellow).bg(Color::DarkGray)
+            theme.value.patch(theme.editing)
         } else {
-            Style::default().fg(Color::Yellow)
+            theme.value
         };
+        let (key_text, key_cursor) = field_view(&pair.key, key_width, editing_key);
+        let (value_text, value_cursor) = field_view(&pair.value, value_width, editing_value);
         let pair_text = Line::from(vec![
             Span::raw("  "),
             Span::styled(
-                format!("{:<width$}", pair.key, width = longest_key),
+                format!("{:<width$}", key_text, width = key_width),
                 key_style,
             ),
             Span::raw(" : "),
-            Span::styled(&pair.value, value_style),
+            Span::styled(value_text, value_style),
         ]);
 
-        let pair_block = Paragraph::new(pair_text)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(border_style)
-                    .border_type(BorderType::Rounded)
-                    .title(format!("Entry {}", i + 1)),
-            )
-            .wrap(Wrap { trim: true });
-
-        frame.render_widget(pair_block, layout_chunks[i + offset]);
+        let pair_area = layout_chunks[i + offset];
+        targets.extend(field_targets(pair_area, key_width, i));
+        if editing_key && show_cursor {
+            place_cursor(frame, pair_area, 2 + key_cursor);
+        } else if editing_value && show_cursor {
+            place_cursor(frame, pair_area, PAIR_PADDING + key_width + value_cursor);
+        }
+
+        let pair = pair.to_pair();
+        let mut pair_border = Block::default()
+            .borders(Borders::ALL)
+            .border_style(border_style)
+            .border_type(BorderType::Rounded)
+            .title(format!("Entry {}", i + 1));
+        if pair.is_sensitive() && !pair.value.is_empty() {
+            pair_border = pair_border.title(
+                strength_bar(&strength::estimate_cached(&pair.value), theme).right_aligned(),
+            );
+        }
+
+        let pair_block = Paragraph::new(pair_text).block(pair_border);
+
+        frame.render_widget(pair_block, pair_area);
     }
 
     // --- New entry field ---
-    let selected_new_entry = matches!(app.currently_editing, Some(CurrentlyEditing::Key(idx)) if pairs_to_render.len() == idx)
-        || matches!(app.currently_editing, Some(CurrentlyEditing::Value(idx)) if pairs_to_render.len() == idx);
-    let new_entry_border_style = if selected_new_entry {
-        Style::default().fg(Color::Cyan)
+    let editing_new_key = matches!(app.currently_editing, Some(CurrentlyEditing::Key(idx)) if pairs_to_render.len() == idx);
+    let editing_new_value = matches!(app.currently_editing, Some(CurrentlyEditing::Value(idx)) if pairs_to_render.len() == idx);
+    let new_entry_border_style = if editing_new_key || editing_new_value {
+        theme.focus
     } else {
-        Style::default().fg(Color::White)
+        theme.border
     };
 
+    let (key_text, key_cursor) = field_view(&app.key_input, key_width, editing_new_key);
+    let (value_text, value_cursor) = field_view(&app.value_input, value_width, editing_new_value);
     let new_entry_text = if app.key_input.is_empty() && app.value_input.is_empty() {
         Line::from(vec![
+            Span::raw("  "),
             Span::styled(
-                format!("{:<width$}", "<new key>", width = longest_key),
-                Style::default().fg(Color::DarkGray),
+                format!("{:<width$}", "<new key>", width = key_width),
+                theme.dim,
             ),
-            Span::styled(" : ", Style::default().fg(Color::DarkGray)),
-            Span::styled("<new value>", Style::default().fg(Color::DarkGray)),
+            Span::styled(" : ", theme.dim),
+            Span::styled("<new value>", theme.dim),
         ])
     } else {
         Line::from(vec![
+            Span::raw("  "),
             Span::styled(
-                format!("{:<width$}", app.key_input, width = longest_key),
-                Style::default().fg(Color::DarkGray),
+                format!("{:<width$}", key_text, width = key_width),
+                theme.dim,
             ),
-            Span::styled(" : ", Style::default().fg(Color::DarkGray)),
-            Span::styled(&app.value_input, Style::default().fg(Color::DarkGray)),
+            Span::styled(" : ", theme.dim),
+            Span::styled(value_text, theme.dim),
         ])
     };
 
-    let new_entry_block = Paragraph::new(new_entry_text).block(
+    let new_entry_area = layout_chunks[pairs_to_render.len() + offset];
+    targets.extend(field_targets(
+        new_entry_area,
+        key_width,
+        pairs_to_render.len(),
+    ));
+    if editing_new_key && show_cursor {
+        place_cursor(frame, new_entry_area, 2 + key_cursor);
+    } else if editing_new_value && show_cursor {
+        place_cursor(
+            frame,
+            new_entry_area,
+            PAIR_PADDING + key_width + value_cursor,
+        );
+    }
+
+    let mut new_entry_border = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(new_entry_border_style)
+        .title("Add new entry");
+    let new_pair = Pair {
+        key: app.key_input.text().to_string(),
+        value: app.value_input.text().to_string(),
+    };
+    if new_pair.is_sensitive() && !new_pair.value.is_empty() {
+        new_entry_border = new_entry_border.title(
+            strength_bar(&strength::estimate_cached(&new_pair.value), theme).right_aligned(),
+        );
+    }
+
+    let new_entry_block = Paragraph::new(new_entry_text).block(new_entry_border);
+
+    frame.render_widget(new_entry_block, new_entry_area);
+
+    // --- Hint ---
+    let hint = Paragraph::new(format!(
+        "TAB to move, <CTRL + Z/Y> to undo/redo, <CTRL + D> to delete an entry (<CTRL + R> brings it back), <{}> to generate a random value ({}, <CTRL + P> to change), ENTER to add the entry, <CTRL + S> to save, ESC to close",
+        app.keymap.hint(Action::Generate),
+        app.active_generator_profile().name
+    ))
+        .alignment(Alignment::Center)
+        .style(theme.dim);
+
+    frame.render_widget(hint, layout_chunks[pairs_to_render.len() + offset + 1]);
+
+    // --- Status ---
+    if let Some(status) = &app.status_message {
+        let status = Paragraph::new(status.as_str())
+            .alignment(Alignment::Center)
+            .style(theme.status);
+        frame.render_widget(status, *layout_chunks.last().unwrap());
+    }
+
+    if app.confirm_discard {
+        render_discard_prompt(frame, theme);
+    }
+    app.click_targets.extend(targets);
+}
+
+fn render_discard_prompt(frame: &mut Frame, theme: &Theme) {
+    let area = centered_rect(40, 20, frame.area());
+    frame.render_widget(Clear, area);
+    let prompt = Paragraph::new(vec![
+        Line::from("Discard unsaved changes?"),
+        Line::default(),
+        Line::styled("(y) discard / (s) save / (n) keep editing", theme.dim),
+    ])
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true })
+    .block(
         Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .border_style(new_entry_border_style)
-            .title("Add new entry"),
+            .border_style(theme.warning)
+            .title("Unsaved changes"),
     );
+    frame.render_widget(prompt, area);
+}
 
-    frame.render_widget(
-        new_entry_block,
-        layout_chunks[pairs_to_render.len() + offset],
-    );
+/// The part of a field that fits in `width`, scrolled to the cursor while
+/// it's being edited and from the start otherwise
+fn field_view(editor: &LineEditor, width: usize, editing: bool) -> (String, usize) {
+    if editing {
+        editor.view(width, None)
+    } else {
+        (editor.text().chars().take(width).collect(), 0)
+    }
+}
 
-    // --- Hint ---
-    let hint = Paragraph::new("TAB to move, <CTRL + G> to generate a random value, ENTER to save and close, ESC to cancel WITHOUT saving")
-        .alignment(Alignment::Center)
-        .style(Style::default().fg(Color::DarkGray));
+/// The key and value halves of an entry, split at the colon
+fn field_targets(area: Rect, key_width: usize, idx: usize) -> [(Rect, ClickTarget); 2] {
+    let split = (PAIR_PADDING - 1 + key_width).min(area.width as usize) as u16;
+    let (key_area, value_area) = (
+        Rect {
+            width: split,
+            ..area
+        },
+        Rect {
+            x: area.x + split,
+            width: area.width - split,
+            ..area
+        },
+    );
+    [
+        (key_area, ClickTarget::Field(CurrentlyEditing::Key(idx))),
+        (value_area, ClickTarget::Field(CurrentlyEditing::Value(idx))),
+    ]
+}
 
-    frame.render_widget(hint, *layout_chunks.last().unwrap());
+/// Put the terminal cursor `column` characters into a bordered row
+fn place_cursor(frame: &mut Frame, area: Rect, column: usize) {
+    frame.set_cursor_position((area.x + 1 + column as u16, area.y + 1));
 }
@@ -0,0 +1,87 @@
+use super::utils::centered_rect;
+use crate::app::App;
+use crate::health::Severity;
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::Modifier,
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+};
+
+pub fn render_health(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    let full_area = centered_rect(70, 80, frame.area());
+    frame.render_widget(Clear, full_area);
+
+    let outer = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(format!("Vault health ({} issues)", app.health_report.len()));
+    let inner_area = outer.inner(full_area);
+    frame.render_widget(outer, full_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner_area);
+
+    // --- Issues, grouped by severity ---
+    let mut text = Text::default();
+    let mut selected_line = 0;
+    if app.health_report.is_empty() {
+        text.push_line(Line::styled("No problems found", theme.success));
+    }
+    for severity in Severity::ALL {
+        let style = match severity {
+            Severity::Critical => theme.danger,
+            Severity::Warning => theme.warning,
+            Severity::Info => theme.info,
+        };
+        let issues: Vec<(usize, _)> = app
+            .health_report
+            .iter()
+            .enumerate()
+            .filter(|(_, issue)| issue.severity == severity)
+            .collect();
+        if issues.is_empty() {
+            continue;
+        }
+
+        text.push_line(Line::styled(
+            format!("{} ({})", severity.name(), issues.len()),
+            style.add_modifier(Modifier::BOLD),
+        ));
+        for (idx, issue) in issues {
+            let selected = Some(idx) == app.currently_selected_issue_idx;
+            if selected {
+                selected_line = text.lines.len();
+            }
+            let name_style = if selected {
+                theme.selected
+            } else {
+                theme.accent
+            };
+            text.push_line(Line::from(vec![
+                Span::raw("  "),
+                Span::styled(issue.secret_name.clone(), name_style),
+                Span::raw("  "),
+                Span::styled(issue.describe(), style),
+            ]));
+        }
+        text.push_line(Line::default());
+    }
+
+    // Keep the selected issue in view
+    let height = chunks[0].height as usize;
+    let scroll = selected_line.saturating_sub(height.saturating_sub(1));
+    let issues = Paragraph::new(text).scroll((scroll as u16, 0));
+    frame.render_widget(issues, chunks[0]);
+
+    // --- Hint ---
+    let hint = Paragraph::new("UP/DOWN to choose, ENTER to open the entry, ESC to close")
+        .alignment(Alignment::Center)
+        .style(theme.dim);
+    frame.render_widget(hint, chunks[1]);
+}
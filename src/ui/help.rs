@@ -0,0 +1,56 @@
+use super::utils::centered_rect;
+use crate::app::App;
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+};
+
+/// Every key that does something where the user is, straight from the keymap
+pub fn render_help(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    let context = app.key_context();
+    let rows = app.keymap.help(context);
+
+    let full_area = centered_rect(60, 80, frame.area());
+    frame.render_widget(Clear, full_area);
+
+    let outer = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.focus)
+        .title(format!("Keys: {}", context.name()));
+    let inner_area = outer.inner(full_area);
+    frame.render_widget(outer, full_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner_area);
+
+    let width = rows
+        .iter()
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut text = Text::default();
+    for (keys, description) in rows {
+        text.push_line(Line::from(vec![
+            Span::styled(format!("{:>width$}", keys, width = width), theme.key),
+            Span::raw("  "),
+            Span::styled(description, theme.text),
+        ]));
+    }
+    if context.typing() {
+        text.push_line(Line::default());
+        text.push_line(Line::styled("Anything else is typed", theme.dim));
+    }
+    frame.render_widget(Paragraph::new(text), chunks[0]);
+
+    let hint = Paragraph::new("Press any key to close")
+        .alignment(Alignment::Center)
+        .style(theme.dim);
+    frame.render_widget(hint, chunks[1]);
+}
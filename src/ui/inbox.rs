@@ -0,0 +1,120 @@
+use super::utils::centered_rect;
+use crate::app::App;
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+};
+
+pub fn render_inbox(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    let full_area = centered_rect(70, 80, frame.area());
+    frame.render_widget(Clear, full_area);
+
+    let outer = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(format!("Inbox ({})", app.pending.len()));
+    let inner_area = outer.inner(full_area);
+    frame.render_widget(outer, full_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner_area);
+
+    // While merging, the secrets to choose from sit beside the inbox
+    let columns = if app.merge_target_idx.is_some() {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[0])
+    } else {
+        Layout::default()
+            .constraints([Constraint::Percentage(100)])
+            .split(chunks[0])
+    };
+
+    // --- Pending credentials ---
+    let mut pending_text = Text::default();
+    if app.pending.is_empty() {
+        pending_text.push_line(Line::styled("Nothing waiting for review", theme.dim));
+    }
+    for (idx, pending) in app.pending.iter().enumerate() {
+        let selected = Some(idx) == app.currently_selected_pending_idx;
+        let name_style = if selected {
+            theme.selected
+        } else {
+            theme.title
+        };
+        pending_text.push_line(Line::from(vec![
+            Span::styled(pending.get_name(), name_style),
+            Span::styled(
+                format!(
+                    "  captured {}",
+                    pending.get_last_modified().format("%Y-%m-%d %H:%M")
+                ),
+                theme.dim,
+            ),
+        ]));
+        for pair in pending.get_contents() {
+            let value = if pair.key == "password" {
+                "*".repeat(pair.value.len())
+            } else {
+                pair.value
+            };
+            pending_text.push_line(Line::from(vec![
+                Span::raw("  "),
+                Span::styled(pair.key, theme.key),
+                Span::raw(" : "),
+                Span::raw(value),
+            ]));
+        }
+        pending_text.push_line(Line::default());
+    }
+
+    let pending_block = Paragraph::new(pending_text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title("Captured credentials"),
+        )
+        .wrap(Wrap { trim: true });
+    frame.render_widget(pending_block, columns[0]);
+
+    // --- Merge targets ---
+    if let Some(target_idx) = app.merge_target_idx {
+        let mut targets_text = Text::default();
+        for (idx, secret) in app.secrets.iter().enumerate() {
+            let style = if idx == target_idx {
+                theme.selected
+            } else {
+                theme.text
+            };
+            targets_text.push_line(Line::styled(secret.get_name(), style));
+        }
+
+        let targets_block = Paragraph::new(targets_text).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(theme.focus)
+                .title("Merge into"),
+        );
+        frame.render_widget(targets_block, columns[1]);
+    }
+
+    // --- Hint ---
+    let hint = if app.merge_target_idx.is_some() {
+        "UP/DOWN to choose a secret, ENTER to replace its username and password, ESC to go back"
+    } else {
+        "(a) accept as new / (m) merge into existing / (d) discard / (ESC) close"
+    };
+    let hint = Paragraph::new(hint)
+        .alignment(Alignment::Center)
+        .style(theme.dim);
+    frame.render_widget(hint, chunks[1]);
+}
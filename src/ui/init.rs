@@ -1,10 +1,12 @@
-use super::utils::centered_rect;
-use crate::app::App;
+use super::utils::{centered_rect, render_line_input, strength_bar};
+use crate::app::{App, InitStep};
+use crate::kdf::KdfProfile;
+use crate::strength;
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    text::Text,
+    style::Modifier,
+    text::{Line, Text},
     widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
 };
 
@@ -20,9 +22,9 @@ pub fn render_init(frame: &mut Frame, app: &App) {
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Percentage(30),
-            Constraint::Percentage(10),
-            Constraint::Percentage(30),
-            Constraint::Percentage(30),
+            Constraint::Percentage(5),
+            Constraint::Percentage(25),
+            Constraint::Min(12), // room for the longest step
         ])
         .split(full_area);
 
@@ -30,43 +32,142 @@ pub fn render_init(frame: &mut Frame, app: &App) {
     let key_area = layout_chunks[2];
     let block_area = layout_chunks[3];
 
+    let step = app.wizard.step;
+    let options: Vec<(bool, String)> = match step {
+        InitStep::Kdf => KdfProfile::ALL
+            .iter()
+            .map(|profile| {
+                (
+                    *profile == app.wizard.kdf,
+                    format!("{:<12} {}", profile.name(), profile.description()),
+                )
+            })
+            .collect(),
+        InitStep::RecoveryKit => vec![
+            (
+                app.wizard.recovery_kit,
+                String::from("Write a recovery kit to print"),
+            ),
+            (!app.wizard.recovery_kit, String::from("Skip it")),
+        ],
+        _ => vec![],
+    };
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .margin(3)
+        .margin(1)
         .constraints([
-            Constraint::Length(3), // input
-            Constraint::Length(1), // spacer
-            Constraint::Length(1), // hint
+            Constraint::Length(1),                               // step
+            Constraint::Length(2 + options.len().max(1) as u16), // input
+            Constraint::Length(1),                               // strength
+            Constraint::Length(1),                               // description
+            Constraint::Length(1),                               // hint
+            Constraint::Length(1),                               // status
         ])
         .split(block_area);
 
-    // masked input
-    let masked_input = "*".repeat(app.scratch.len());
-    let input_paragraph = Paragraph::new(masked_input)
-        .style(Style::default().fg(Color::Yellow))
-        .alignment(ratatui::layout::Alignment::Center)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::White))
-                .border_type(BorderType::Rounded)
-                .title("Master Password"),
+    let theme = &app.theme;
+    let steps = app.wizard.steps();
+    let number = steps.iter().position(|s| *s == step).unwrap_or(0) + 1;
+    let resetting = app.wizard.recovery_code.is_some();
+    let (title, description) = match step {
+        InitStep::Password if resetting => (
+            "New Master Password",
+            "Choose a password to replace the forgotten one",
+        ),
+        InitStep::Password => (
+            "Master Password",
+            "Choose a password, and don't forget it...",
+        ),
+        InitStep::Confirm => ("Confirm Master Password", "Type it again to make sure..."),
+        InitStep::Hint => (
+            "Hint (optional)",
+            "Shown when unlocking and stored unencrypted, so keep it vague",
+        ),
+        InitStep::Location => (
+            "Vault Folder",
+            "Where the vault and the password hash are kept",
+        ),
+        InitStep::Kdf => (
+            "Key Derivation",
+            "Slower makes guessing the password from a stolen hash slower too",
+        ),
+        InitStep::RecoveryKit => (
+            "Recovery Kit",
+            "A code that opens the vault if you forget the password, to print",
+        ),
+    };
+    let progress = Paragraph::new(format!("Step {} of {}", number, steps.len()))
+        .style(theme.dim)
+        .alignment(ratatui::layout::Alignment::Center);
+    frame.render_widget(progress, chunks[0]);
+
+    let input_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme.border)
+        .border_type(BorderType::Rounded)
+        .title(title);
+    if step.typing() {
+        let mask = matches!(step, InitStep::Password | InitStep::Confirm).then_some('*');
+        render_line_input(
+            frame,
+            &app.scratch,
+            input_block,
+            chunks[1],
+            theme.input,
+            mask,
+            true,
         );
-    frame.render_widget(input_paragraph, chunks[0]);
+    } else {
+        let lines: Vec<Line> = options
+            .into_iter()
+            .map(|(chosen, label)| {
+                if chosen {
+                    Line::styled(format!("> {}", label), theme.selected)
+                } else {
+                    Line::styled(format!("  {}", label), theme.text)
+                }
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(input_block), chunks[1]);
+    }
+
+    // strength, only worth judging the first time round
+    if step == InitStep::Password && !app.scratch.is_empty() {
+        let strength = strength::estimate_cached(app.scratch.text());
+        let bar = Paragraph::new(strength_bar(&strength, theme))
+            .alignment(ratatui::layout::Alignment::Center);
+        frame.render_widget(bar, chunks[2]);
+    }
 
-    let description_paragraph = Paragraph::new("Choose a password, and don't forget it...")
-        .style(Style::default())
+    let description_paragraph = Paragraph::new(description)
+        .style(theme.text)
         .alignment(ratatui::layout::Alignment::Center);
-    frame.render_widget(description_paragraph, chunks[1]);
+    frame.render_widget(description_paragraph, chunks[3]);
 
     // hint
-    let hint = Paragraph::new("Press Enter to save, or ESC to quit.")
-        .style(Style::default().fg(Color::DarkGray))
-        .alignment(ratatui::layout::Alignment::Center);
-    frame.render_widget(hint, chunks[2]);
+    let hint = Paragraph::new(match step {
+        InitStep::Password => "Press Enter to continue, or ESC to quit.",
+        InitStep::Kdf if resetting => {
+            "UP/DOWN to choose, Enter to change the password, ESC to go back."
+        }
+        InitStep::Kdf => "UP/DOWN to choose, Enter to continue, ESC to go back.",
+        InitStep::RecoveryKit => "UP/DOWN to choose, Enter to create the vault, ESC to go back.",
+        _ => "Press Enter to continue, or ESC to go back.",
+    })
+    .style(theme.dim)
+    .alignment(ratatui::layout::Alignment::Center);
+    frame.render_widget(hint, chunks[4]);
+
+    if let Some(status) = &app.status_message {
+        let status = Paragraph::new(status.as_str())
+            .style(theme.status)
+            .alignment(ratatui::layout::Alignment::Center);
+        frame.render_widget(status, chunks[5]);
+    }
 
     let title_text = Paragraph::new(Text::from(TITLE_ART))
-        .style(Style::default().fg(Color::Cyan))
+        .style(theme.accent)
         .alignment(ratatui::layout::Alignment::Center);
     frame.render_widget(title_text, title_area);
 
@@ -80,11 +181,7 @@ pub fn render_init(frame: &mut Frame, app: &App) {
         .split(key_area);
 
     let art_paragraph = Paragraph::new(KEY_ART)
-        .style(
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::SLOW_BLINK),
-        )
+        .style(theme.accent.add_modifier(Modifier::SLOW_BLINK))
         .wrap(Wrap { trim: false })
         .alignment(ratatui::layout::Alignment::Left);
 
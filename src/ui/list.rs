@@ -0,0 +1,156 @@
+use crate::app::{App, ClickTarget, GridViewport};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState, Wrap},
+};
+
+/// One line per secret: name, tags and when it was last changed
+pub fn render_secret_list(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.theme.clone();
+    let visible = app.visible_secrets();
+    let selected_pos = app
+        .currently_selected_secret_idx
+        .and_then(|idx| visible.iter().position(|v| *v == idx));
+
+    let rows: Vec<Row> = visible
+        .iter()
+        .map(|idx| {
+            let secret = &app.secrets[*idx];
+            let mut name = vec![];
+            if secret.is_favorite() {
+                name.push(Span::styled("★ ", theme.warning));
+            }
+            name.push(Span::raw(secret.get_name().to_string()));
+            if app.breached.contains_key(secret.get_id()) {
+                name.push(Span::styled(" ⚠", theme.danger));
+            }
+            let tags: Vec<String> = secret
+                .get_tags()
+                .iter()
+                .map(|tag| format!("#{}", tag))
+                .collect();
+            Row::new(vec![
+                Line::from(name).style(theme.text),
+                Line::styled(tags.join(" "), theme.dim),
+                Line::styled(
+                    secret.get_last_modified().format("%Y-%m-%d").to_string(),
+                    theme.dim,
+                ),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(16),
+            Constraint::Percentage(30),
+            Constraint::Length(10),
+        ],
+    )
+    .row_highlight_style(theme.selected)
+    .block(Block::default().borders(Borders::ALL).title("Secrets"));
+
+    // Carry the scroll position over from the last frame
+    let mut state = TableState::default()
+        .with_offset(app.grid.first_row)
+        .with_selected(selected_pos);
+    frame.render_stateful_widget(table, area, &mut state);
+
+    app.grid = GridViewport {
+        columns: 1,
+        page_rows: (area.height.saturating_sub(2) as usize).max(1),
+        first_row: state.offset(),
+    };
+    let shown = visible.iter().skip(state.offset()).take(app.grid.page_rows);
+    for (i, idx) in shown.enumerate() {
+        let row = Rect::new(
+            area.x + 1,
+            area.y + 1 + i as u16,
+            area.width.saturating_sub(2),
+            1,
+        );
+        app.click_targets.push((row, ClickTarget::Secret(*idx)));
+    }
+}
+
+/// Every field of the selected secret, sensitive values masked
+pub fn render_secret_detail(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = &app.theme;
+    let block = Block::default().borders(Borders::ALL).title("Details");
+    let Some(secret) = app
+        .currently_selected_secret_idx
+        .and_then(|idx| app.secrets.get(idx))
+    else {
+        let empty = Paragraph::new("Select a secret to see it here")
+            .style(theme.dim)
+            .block(block);
+        frame.render_widget(empty, area);
+        return;
+    };
+
+    let mut text = Text::default();
+    text.push_line(Line::styled(secret.get_name().to_string(), theme.title));
+    if let Some(count) = app.breached.get(secret.get_id()) {
+        text.push_line(Line::styled(
+            format!("⚠ Password seen {} times in data breaches", count),
+            theme.danger,
+        ));
+    }
+    text.push_line(Line::default());
+
+    let contents = secret.get_contents();
+    let longest_key = contents.iter().map(|p| p.key.len()).max().unwrap_or(0);
+    for pair in &contents {
+        let value = if pair.is_sensitive() && !app.reveal_sensitive {
+            "•".repeat(8)
+        } else {
+            pair.value.clone()
+        };
+        text.push_line(Line::from(vec![
+            Span::styled(
+                format!("{:<width$}", pair.key, width = longest_key),
+                theme.key,
+            ),
+            Span::raw(" : "),
+            Span::styled(value, theme.value),
+        ]));
+    }
+
+    text.push_line(Line::default());
+    let dim = theme.dim;
+    if let Some(folder) = secret.get_folder() {
+        text.push_line(Line::styled(format!("Folder    {}", folder), dim));
+    }
+    if !secret.get_tags().is_empty() {
+        text.push_line(Line::styled(
+            format!("Tags      {}", secret.get_tags().join(", ")),
+            dim,
+        ));
+    }
+    text.push_line(Line::styled(
+        format!(
+            "Created   {}",
+            secret.get_created().format("%Y-%m-%d %H:%M")
+        ),
+        dim,
+    ));
+    text.push_line(Line::styled(
+        format!(
+            "Modified  {}",
+            secret.get_last_modified().format("%Y-%m-%d %H:%M")
+        ),
+        dim,
+    ));
+    if let Some(last_used) = secret.get_last_used() {
+        text.push_line(Line::styled(
+            format!("Used      {}", last_used.format("%Y-%m-%d %H:%M")),
+            dim,
+        ));
+    }
+
+    let detail = Paragraph::new(text).block(block).wrap(Wrap { trim: false });
+    frame.render_widget(detail, area);
+}
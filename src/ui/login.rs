@@ -1,9 +1,9 @@
-use super::utils::centered_rect;
+use super::utils::{centered_rect, render_line_input};
 use crate::app::App;
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    style::Modifier,
     text::Text,
     widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
 };
@@ -34,33 +34,67 @@ pub fn render_login(frame: &mut Frame, app: &App) {
         .margin(2)
         .constraints([
             Constraint::Length(3), // input
-            Constraint::Length(1), // spacer
+            Constraint::Length(1), // password hint
             Constraint::Length(1), // hint
+            Constraint::Length(1), // wrong password or back-off
         ])
         .split(block_area);
 
-    // masked input
-    let masked_input = "*".repeat(app.scratch.len());
-    let input_paragraph = Paragraph::new(masked_input)
-        .style(Style::default().fg(Color::Yellow))
-        .alignment(ratatui::layout::Alignment::Center)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::White))
-                .border_type(BorderType::Rounded)
-                .title("Master Password"),
-        );
-    frame.render_widget(input_paragraph, chunks[0]);
+    let theme = &app.theme;
+
+    // masked input, but the recovery code is copied off paper so it's shown
+    let input_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme.border)
+        .border_type(BorderType::Rounded)
+        .title(if app.recovering {
+            "Recovery Code"
+        } else {
+            "Master Password"
+        });
+    render_line_input(
+        frame,
+        &app.scratch,
+        input_block,
+        chunks[0],
+        theme.input,
+        (!app.recovering).then_some('*'),
+        true,
+    );
+
+    if let Some(password_hint) = app.password_hint.as_ref().filter(|_| !app.recovering) {
+        let password_hint = Paragraph::new(format!("Hint: {}", password_hint))
+            .style(theme.dim)
+            .alignment(ratatui::layout::Alignment::Center);
+        frame.render_widget(password_hint, chunks[1]);
+    }
 
     // hint
-    let hint = Paragraph::new("Press Enter to unlock, or ESC to quit.")
-        .style(Style::default().fg(Color::DarkGray))
-        .alignment(ratatui::layout::Alignment::Center);
+    let hint = Paragraph::new(if app.recovering {
+        "Type the code from your recovery kit and press Enter, or Ctrl+R to go back."
+    } else if app.has_recovery_kit() {
+        "Press Enter to unlock, Ctrl+R to use the recovery kit, or ESC to quit."
+    } else {
+        "Press Enter to unlock, or ESC to quit."
+    })
+    .style(theme.dim)
+    .alignment(ratatui::layout::Alignment::Center);
     frame.render_widget(hint, chunks[2]);
 
+    // once the last message is gone, counts down any wait for the next try
+    let status = app
+        .status_message
+        .clone()
+        .or_else(|| app.login_attempts.refusal(&app.config.login));
+    if let Some(status) = status {
+        let status = Paragraph::new(status)
+            .style(theme.danger)
+            .alignment(ratatui::layout::Alignment::Center);
+        frame.render_widget(status, chunks[3]);
+    }
+
     let title_text = Paragraph::new(Text::from(TITLE_ART))
-        .style(Style::default().fg(Color::Cyan))
+        .style(theme.accent)
         .alignment(ratatui::layout::Alignment::Center);
     frame.render_widget(title_text, title_area);
 
@@ -74,11 +108,7 @@ pub fn render_login(frame: &mut Frame, app: &App) {
         .split(lock_area);
 
     let art_paragraph = Paragraph::new(LOCK_ART)
-        .style(
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::SLOW_BLINK),
-        )
+        .style(theme.accent.add_modifier(Modifier::SLOW_BLINK))
         .wrap(Wrap { trim: false })
         .alignment(ratatui::layout::Alignment::Left);
 
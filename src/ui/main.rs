@@ -1,14 +1,22 @@
-use crate::app::{App, CurrentScreen};
+use super::list::{render_secret_detail, render_secret_list};
+use crate::app::{App, ClickTarget, CurrentScreen, Filter, GridViewport};
+use crate::config::ViewMode;
+use crate::keymap::Action;
 use crate::secret::Secret;
 use ratatui::{
     Frame,
-    layout::{Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    layout::{Constraint, Direction, Layout, Margin, Rect},
+    style::Modifier,
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
 };
 
-pub fn render_main(frame: &mut Frame, app: &App) {
+/// Lines each card takes, borders included
+const CARD_HEIGHT: u16 = 9;
+/// Narrowest a card gets when the column count is automatic
+const MIN_CARD_WIDTH: u16 = 30;
+
+pub fn render_main(frame: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -18,92 +26,223 @@ pub fn render_main(frame: &mut Frame, app: &App) {
         ])
         .split(frame.area());
 
-    render_title(frame, chunks[0]);
-    render_secret_grid(frame, app, chunks[1]);
+    let body = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(24), Constraint::Min(1)])
+        .split(chunks[1]);
+
+    render_title(frame, app, chunks[0]);
+    render_sidebar(frame, app, body[0]);
+    match app.config.view {
+        ViewMode::Grid => render_secret_grid(frame, app, body[1]),
+        ViewMode::List => {
+            let panes = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+                .split(body[1]);
+            render_secret_list(frame, app, panes[0]);
+            render_secret_detail(frame, app, panes[1]);
+        }
+    }
     render_footer(frame, app, chunks[2]);
 }
 
-pub fn render_title(frame: &mut Frame, area: Rect) {
+pub fn render_sidebar(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = &app.theme;
+    let mut text = Text::default();
+    let mut targets = vec![];
+    for (i, filter) in app.filters().into_iter().enumerate() {
+        let label = match &filter {
+            Filter::All => String::from("All"),
+            Filter::Favorites => String::from("★ Favorites"),
+            Filter::Folder(folder) => {
+                // Subfolders are indented under their parent
+                let depth = folder.matches('/').count();
+                let name = folder.rsplit('/').next().unwrap_or(folder);
+                format!("{}▸ {}", "  ".repeat(depth), name)
+            }
+            Filter::Tag(tag) => format!("# {}", tag),
+        };
+        let style = if filter == app.filter {
+            if app.sidebar_focused {
+                theme.selected
+            } else {
+                theme.title
+            }
+        } else {
+            theme.text
+        };
+        text.push_line(Line::styled(label, style));
+        let row = Rect::new(
+            area.x + 1,
+            area.y + 1 + i as u16,
+            area.width.saturating_sub(2),
+            1,
+        );
+        if row.bottom() < area.bottom() {
+            targets.push((row, ClickTarget::Filter(filter)));
+        }
+    }
+
+    let border_style = if app.sidebar_focused {
+        theme.focus
+    } else {
+        theme.border
+    };
+    let sidebar = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(border_style)
+            .title("Filter"),
+    );
+    frame.render_widget(sidebar, area);
+    app.click_targets.extend(targets);
+}
+
+pub fn render_title(frame: &mut Frame, app: &App, area: Rect) {
     let title = Paragraph::new("Grimoire")
         .block(
-            Block::default().borders(Borders::ALL).style(
-                Style::default()
-                    .add_modifier(Modifier::BOLD)
-                    .fg(Color::White),
-            ),
+            Block::default()
+                .borders(Borders::ALL)
+                .style(app.theme.text.add_modifier(Modifier::BOLD)),
         )
         .alignment(ratatui::layout::Alignment::Center);
     frame.render_widget(title, area);
 }
 
-pub fn render_secret_grid(frame: &mut Frame, app: &App, area: Rect) {
-    let secrets = &app.secrets;
-    let total = secrets.len();
-    let cols = app.config.secrets_per_row;
-    let rows = (total + cols - 1) / cols;
+/// Draw the rows of cards that fit, scrolled to keep the selection in view
+pub fn render_secret_grid(frame: &mut Frame, app: &mut App, area: Rect) {
+    let inner = area.inner(Margin::new(1, 1));
+    // The last column is kept for the scrollbar
+    let cols = app
+        .config
+        .secrets_per_row
+        .unwrap_or((inner.width.saturating_sub(1) / MIN_CARD_WIDTH) as usize)
+        .max(1);
+    let page_rows = ((inner.height / CARD_HEIGHT) as usize).max(1);
+
+    let visible = app.visible_secrets();
+    let total = visible.len();
+    let rows = total.div_ceil(cols);
+
+    let mut first_row = app.grid.first_row;
+    let selected_pos = app
+        .currently_selected_secret_idx
+        .and_then(|idx| visible.iter().position(|v| *v == idx));
+    if let Some(pos) = selected_pos {
+        let row = pos / cols;
+        if row < first_row {
+            first_row = row;
+        } else if row >= first_row + page_rows {
+            first_row = row + 1 - page_rows;
+        }
+    }
+    first_row = first_row.min(rows.saturating_sub(page_rows));
+    app.grid = GridViewport {
+        columns: cols,
+        page_rows,
+        first_row,
+    };
 
-    let row_constraints = vec![Constraint::Length(9); rows];
+    let shown_rows = rows.saturating_sub(first_row).min(page_rows);
+    let row_constraints = vec![Constraint::Length(CARD_HEIGHT); shown_rows];
+    let grid_area = Rect {
+        width: inner.width.saturating_sub(1),
+        ..inner
+    };
     let row_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .margin(1)
         .constraints(row_constraints)
-        .split(area);
+        .split(grid_area);
 
-    for (row_idx, row_chunk) in row_chunks.iter().enumerate() {
+    for (i, row_chunk) in row_chunks.iter().enumerate() {
+        let row_idx = first_row + i;
         let start = row_idx * cols;
         let end = ((row_idx + 1) * cols).min(total);
-        let row = &secrets[start..end];
+        let row = &visible[start..end];
 
-        let col_constraints = vec![Constraint::Ratio(1, cols as u32); row.len()];
+        // Cards keep the same width on a short last row
+        let col_constraints = vec![Constraint::Ratio(1, cols as u32); cols];
         let col_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints(col_constraints)
             .split(*row_chunk);
 
-        for (i, secret) in row.iter().enumerate() {
-            render_secret_card(frame, app, secret, start + i, col_chunks[i]);
+        for (i, idx) in row.iter().enumerate() {
+            render_secret_card(frame, app, &app.secrets[*idx], *idx, col_chunks[i]);
+            app.click_targets
+                .push((col_chunks[i], ClickTarget::Secret(*idx)));
         }
     }
+
+    if rows > page_rows {
+        let mut scrollbar_state = ScrollbarState::new(rows.saturating_sub(page_rows) + 1)
+            .position(first_row)
+            .viewport_content_length(page_rows);
+        frame.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight),
+            inner,
+            &mut scrollbar_state,
+        );
+    }
 }
 
 pub fn render_secret_card(frame: &mut Frame, app: &App, secret: &Secret, idx: usize, area: Rect) {
+    let theme = &app.theme;
     let selected = Some(idx) == app.currently_selected_secret_idx;
-    let style = if selected {
-        Style::default().fg(Color::Black).bg(Color::Cyan)
-    } else {
-        Style::default().fg(Color::White)
-    };
+    let style = if selected { theme.selected } else { theme.text };
 
     let pairs_to_render = secret.get_contents();
 
     let longest_key = pairs_to_render
         .iter()
         .map(|p| p.key.len())
-        .chain(std::iter::once(app.key_input.len()))
+        .chain(std::iter::once(app.key_input.text().len()))
         .max()
         .unwrap_or(0);
 
     let mut text = Text::default();
     for pair in secret.get_contents() {
+        let value = if pair.is_sensitive() && !app.reveal_sensitive {
+            "•".repeat(8)
+        } else {
+            pair.value
+        };
         text.push_line(Line::from(vec![
             Span::styled(
                 format!("{:<width$}", pair.key, width = longest_key),
-                Style::default().fg(Color::Yellow),
+                theme.key,
             ),
             Span::raw(" : "),
-            Span::raw(pair.value),
+            Span::styled(value, theme.value),
         ]));
     }
 
+    let mut title = Vec::new();
+    if secret.is_favorite() {
+        title.push(Span::styled("★ ", theme.warning));
+    }
+    title.push(Span::styled(secret.get_name(), theme.title));
+    if app.breached.contains_key(secret.get_id()) {
+        title.push(Span::styled(
+            " ⚠ breached",
+            theme.danger.add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    // Folder and tags along the bottom edge
+    let mut labels: Vec<String> = secret
+        .get_folder()
+        .map(|folder| format!("{}/", folder))
+        .into_iter()
+        .collect();
+    labels.extend(secret.get_tags().iter().map(|tag| format!("#{}", tag)));
+
     let paragraph = Paragraph::new(text)
         .block(
             Block::default()
-                .title(Span::styled(
-                    secret.get_name(),
-                    Style::default()
-                        .fg(Color::Cyan)
-                        .add_modifier(Modifier::BOLD),
-                ))
+                .title(Line::from(title))
+                .title_bottom(Line::styled(labels.join(" "), theme.dim))
                 .borders(Borders::ALL)
                 .style(style),
         )
@@ -113,23 +252,51 @@ pub fn render_secret_card(frame: &mut Frame, app: &App, secret: &Secret, idx: us
 
 pub fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
     let hint = match app.current_screen {
-        CurrentScreen::Main => {
-            "(q) to quit / (n) to make new secret / (/) to search / (x) to delete"
+        CurrentScreen::Main if app.sidebar_focused => {
+            "(UP/DOWN) choose filter / (Tab) back to secrets / (?) all keys"
+        }
+        CurrentScreen::Main => &format!(
+            "({}) new / ({}) search / ({}) delete / (s) sort [{}] / (i) inbox [{}] / (b) trash [{}] / ({}) reveal / ({}) lock / ({}) quit / (?) all keys",
+            app.keymap.hint(Action::New),
+            app.keymap.hint(Action::Search),
+            app.keymap.hint(Action::Delete),
+            app.config.sort_mode.label(),
+            app.pending.len(),
+            app.trash.len(),
+            app.keymap.hint(Action::Reveal),
+            app.keymap.hint(Action::Lock),
+            app.keymap.hint(Action::Quit)
+        ),
+        CurrentScreen::EditingTags => &format!(
+            "Tags, comma separated: {} - (Enter) save / (ESC) cancel",
+            app.scratch.text()
+        ),
+        CurrentScreen::EditingFolder => &format!(
+            "Folder, e.g. work/servers: {} - (Enter) save / (ESC) cancel",
+            app.scratch.text()
+        ),
+        CurrentScreen::Jumping => {
+            &format!("Jump to: {} - (Enter) or (ESC) to stop", app.scratch.text())
         }
         CurrentScreen::Searching => &format!(
             "{} - (Tab) to find next match / (ESC) to cancel",
-            &app.scratch
+            app.scratch.text()
+        ),
+        CurrentScreen::New | CurrentScreen::Editing => &format!(
+            "(Ctrl+S) save / (ESC) close / (Tab) switch / (Enter) add entry / ({}) copy / ({}) paste / (F1) all keys",
+            app.keymap.hint(Action::Copy),
+            app.keymap.hint(Action::Paste)
         ),
-        CurrentScreen::New => "(ESC) cancel / (Tab) switch / Enter complete",
-        CurrentScreen::Editing => "(q) quit / (e) new pair",
 
         _ => "",
     };
 
-    let footer = Paragraph::new(Line::from(Span::styled(
-        hint,
-        Style::default().fg(Color::Red),
-    )))
-    .block(Block::default().borders(Borders::ALL));
+    let mut spans = vec![Span::styled(hint, app.theme.footer)];
+    if let Some(status) = &app.status_message {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(status, app.theme.status));
+    }
+
+    let footer = Paragraph::new(Line::from(spans)).block(Block::default().borders(Borders::ALL));
     frame.render_widget(footer, area);
 }
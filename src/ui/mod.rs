@@ -1,23 +1,55 @@
 mod edit;
+mod health;
+mod help;
+mod inbox;
 mod init;
+mod list;
 mod login;
 mod main;
+mod trash;
 mod utils;
 
+pub use crate::line_editor::LineEditor;
 pub use edit::render_edit_popup;
+pub use health::render_health;
+pub use help::render_help;
+pub use inbox::render_inbox;
 pub use init::render_init;
 pub use login::render_login;
 pub use main::render_main;
+pub use trash::{render_delete_confirmation, render_purge_confirmation, render_trash};
 
 use crate::app::App;
 use crate::app::CurrentScreen;
 use ratatui::Frame;
 
 // Entry point
-pub fn ui(frame: &mut Frame, app: &App) {
+pub fn ui(frame: &mut Frame, app: &mut App) {
+    app.click_targets.clear();
     match app.current_screen {
         CurrentScreen::Login => render_login(frame, app),
         CurrentScreen::Init => render_init(frame, app),
+        CurrentScreen::Inbox => {
+            render_main(frame, app);
+            render_inbox(frame, app);
+        }
+        CurrentScreen::Health => {
+            render_main(frame, app);
+            render_health(frame, app);
+        }
+        CurrentScreen::Trash => {
+            render_main(frame, app);
+            render_trash(frame, app);
+        }
+        CurrentScreen::ConfirmPurge => {
+            render_main(frame, app);
+            render_trash(frame, app);
+            render_purge_confirmation(frame, app);
+        }
+        CurrentScreen::ConfirmDelete => {
+            render_main(frame, app);
+            render_delete_confirmation(frame, app);
+        }
         _ => render_main(frame, app),
     }
 
@@ -25,4 +57,8 @@ pub fn ui(frame: &mut Frame, app: &App) {
         // Please review this. Why is this different from above?
         render_edit_popup(frame, app);
     }
+
+    if app.show_help {
+        render_help(frame, app);
+    }
 }
@@ -0,0 +1,124 @@
+use super::utils::centered_rect;
+use crate::app::App;
+use chrono::Local;
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap},
+};
+
+pub fn render_trash(frame: &mut Frame, app: &App) {
+    let theme = &app.theme;
+    let full_area = centered_rect(70, 80, frame.area());
+    frame.render_widget(Clear, full_area);
+
+    let outer = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(format!("Trash ({})", app.trash.len()));
+    let inner_area = outer.inner(full_area);
+    frame.render_widget(outer, full_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Min(1), Constraint::Length(1)])
+        .split(inner_area);
+
+    // --- Deleted secrets ---
+    let mut text = Text::default();
+    let mut selected_line = 0;
+    if app.trash.is_empty() {
+        text.push_line(Line::styled("The trash is empty", theme.dim));
+    }
+    let retention = app.config.trash_retention_days as i64;
+    for (idx, secret) in app.trash.iter().enumerate() {
+        let selected = Some(idx) == app.currently_selected_trash_idx;
+        if selected {
+            selected_line = text.lines.len();
+        }
+        let name_style = if selected {
+            theme.selected
+        } else {
+            theme.title
+        };
+        let mut line = vec![Span::styled(secret.get_name(), name_style)];
+        if let Some(deleted) = secret.get_deleted() {
+            let mut when = format!("  deleted {}", deleted.format("%Y-%m-%d %H:%M"));
+            if retention > 0 {
+                let left = retention - (Local::now() - deleted).num_days();
+                when.push_str(&format!(", purged in {} days", left.max(0)));
+            }
+            line.push(Span::styled(when, theme.dim));
+        }
+        text.push_line(Line::from(line));
+    }
+
+    // Keep the selected secret in view
+    let height = chunks[0].height as usize;
+    let scroll = selected_line.saturating_sub(height.saturating_sub(1));
+    let trash = Paragraph::new(text).scroll((scroll as u16, 0));
+    frame.render_widget(trash, chunks[0]);
+
+    // --- Hint ---
+    let hint = Paragraph::new("(r) restore / (p) delete for good / (ESC) close")
+        .alignment(Alignment::Center)
+        .style(theme.dim);
+    frame.render_widget(hint, chunks[1]);
+}
+
+pub fn render_delete_confirmation(frame: &mut Frame, app: &App) {
+    let Some(secret) = app
+        .currently_selected_secret_idx
+        .and_then(|idx| app.secrets.get(idx))
+    else {
+        return;
+    };
+    let theme = &app.theme;
+    let area = centered_rect(40, 20, frame.area());
+    frame.render_widget(Clear, area);
+    let prompt = Paragraph::new(vec![
+        Line::from(format!("Move {} to the trash?", secret.get_name())),
+        Line::default(),
+        Line::styled("(y) move it / (n) keep it", theme.dim),
+    ])
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true })
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(theme.danger)
+            .title("Delete"),
+    );
+    frame.render_widget(prompt, area);
+}
+
+pub fn render_purge_confirmation(frame: &mut Frame, app: &App) {
+    let Some(secret) = app
+        .currently_selected_trash_idx
+        .and_then(|idx| app.trash.get(idx))
+    else {
+        return;
+    };
+    let theme = &app.theme;
+    let area = centered_rect(40, 20, frame.area());
+    frame.render_widget(Clear, area);
+    let prompt = Paragraph::new(vec![
+        Line::from(format!("Delete {} for good?", secret.get_name())),
+        Line::styled("This can't be undone", theme.danger),
+        Line::default(),
+        Line::styled("(y) delete it / (n) keep it", theme.dim),
+    ])
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true })
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(theme.danger)
+            .title("Delete for good"),
+    );
+    frame.render_widget(prompt, area);
+}
@@ -1,4 +1,13 @@
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use super::LineEditor;
+use crate::strength::Strength;
+use crate::theme::Theme;
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+};
 
 pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let vertical = Layout::default()
@@ -19,3 +28,46 @@ pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
         ])
         .split(vertical[1])[1]
 }
+
+/// Five blocks filled by score, then the label
+pub fn strength_bar(strength: &Strength, theme: &Theme) -> Line<'static> {
+    let style = match strength.score {
+        0 | 1 => theme.danger,
+        2 => theme.warning,
+        _ => theme.success,
+    };
+    let filled = strength.score as usize + 1;
+    Line::from(vec![
+        Span::styled("█".repeat(filled), style),
+        Span::styled("░".repeat(5 - filled), theme.dim),
+        Span::styled(
+            format!(" {} ({:.0} bits)", strength.label(), strength.entropy),
+            style,
+        ),
+    ])
+}
+
+/// Draw a one-line input centered in its block, putting the terminal
+/// cursor on the editor's cursor when `focused`
+pub fn render_line_input(
+    frame: &mut Frame,
+    editor: &LineEditor,
+    block: Block,
+    area: Rect,
+    style: Style,
+    mask: Option<char>,
+    focused: bool,
+) {
+    let inner = block.inner(area);
+    let (text, cursor) = editor.view(inner.width as usize, mask);
+    // Where the centered text starts
+    let left = inner.x + inner.width.saturating_sub(text.chars().count() as u16) / 2;
+    let input = Paragraph::new(text)
+        .style(style)
+        .alignment(Alignment::Center)
+        .block(block);
+    frame.render_widget(input, area);
+    if focused {
+        frame.set_cursor_position((left + cursor as u16, inner.y));
+    }
+}
@@ -0,0 +1,50 @@
+use std::fs;
+
+/// The EFF wordlists, when built with the `eff-wordlists` feature
+#[cfg(feature = "eff-wordlists")]
+const EFF_LARGE: Option<&str> = Some(include_str!("../assets/wordlists/eff_large_wordlist.txt"));
+#[cfg(not(feature = "eff-wordlists"))]
+const EFF_LARGE: Option<&str> = None;
+
+#[cfg(feature = "eff-wordlists")]
+const EFF_SHORT: Option<&str> = Some(include_str!("../assets/wordlists/eff_short_wordlist_1.txt"));
+#[cfg(not(feature = "eff-wordlists"))]
+const EFF_SHORT: Option<&str> = None;
+
+/// Load a wordlist by name: "eff_large", "eff_short" or the path of a file
+/// with one word per line, optionally prefixed by its dice roll
+pub fn load(name: &str) -> Result<Vec<String>, String> {
+    let text = match name {
+        "eff_large" | "eff_short" => {
+            let bundled = if name == "eff_large" {
+                EFF_LARGE
+            } else {
+                EFF_SHORT
+            };
+            match bundled {
+                Some(text) => text.to_string(),
+                None => {
+                    return Err(format!(
+                        "{} isn't bundled in this build, enable the eff-wordlists feature or set wordlist to a file",
+                        name
+                    ));
+                }
+            }
+        }
+        path => fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read wordlist {}: {}", path, e))?,
+    };
+
+    let mut words: Vec<String> = text
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        .map(String::from)
+        .collect();
+    words.sort();
+    words.dedup();
+
+    if words.len() < 2 {
+        return Err(format!("Wordlist {} has too few words", name));
+    }
+    Ok(words)
+}